@@ -1,27 +1,18 @@
+use binance::ticker::{Custom, Serde, SerdeBorrowed, SerdeLazy, Sonic, TickerParser};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
+fn bench_parser<P: TickerParser>(c: &mut Criterion, name: &str, data: &str) {
+    c.bench_function(name, |b| b.iter(|| P::parse(black_box(data))));
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     let test_input_str = include_str!("../single.txt");
 
-    c.bench_function("serde", |b| {
-        b.iter(|| binance::serde::parse(black_box(test_input_str)))
-    });
-
-    c.bench_function("serde_borrowed", |b| {
-        b.iter(|| binance::serde_borrowed::parse(black_box(test_input_str)))
-    });
-
-    c.bench_function("serde_lazy", |b| {
-        b.iter(|| binance::serde_lazy::parse(black_box(test_input_str)))
-    });
-
-    c.bench_function("sonic", |b| {
-        b.iter(|| binance::sonic::parse(black_box(test_input_str)))
-    });
-
-    c.bench_function("custom", |b| {
-        b.iter(|| binance::custom::parse(black_box(test_input_str)))
-    });
+    bench_parser::<Serde>(c, "serde", test_input_str);
+    bench_parser::<SerdeBorrowed>(c, "serde_borrowed", test_input_str);
+    bench_parser::<SerdeLazy>(c, "serde_lazy", test_input_str);
+    bench_parser::<Sonic>(c, "sonic", test_input_str);
+    bench_parser::<Custom>(c, "custom", test_input_str);
 
     c.bench_function("custom_lazy", |b| {
         b.iter(|| {
@@ -41,6 +32,100 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 .unwrap()
         })
     });
+
+    // The same target types as `serde_borrowed` and `serde_lazy`, filled by the lazy parser.
+    c.bench_function("custom_lazy_serde_borrowed", |b| {
+        b.iter(|| {
+            binance::custom_lazy::from_str::<Vec<binance::serde_borrowed::PriceChange24Hr>>(
+                black_box(test_input_str),
+            )
+        })
+    });
+
+    c.bench_function("custom_lazy_serde_lazy", |b| {
+        b.iter(|| {
+            binance::custom_lazy::from_str::<Vec<binance::serde_lazy::PriceChange24Hr>>(black_box(
+                test_input_str,
+            ))
+        })
+    });
+
+    let large_input_str = include_str!("../request.txt");
+    let num_entries = binance::custom_lazy::Document::new(large_input_str)
+        .as_array()
+        .unwrap()
+        .len()
+        .unwrap();
+
+    // Visit every entry of the array using `get_index`, both in order and in a pseudo-random
+    // order. The sequential access benefits from the array remembering its last position.
+    let sequential: Vec<usize> = (0..num_entries).collect();
+    let mut random = sequential.clone();
+    let mut state = 0x2545f491u64;
+    for i in (1..random.len()).rev() {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+        random.swap(i, (state >> 33) as usize % (i + 1));
+    }
+
+    let index = binance::custom_lazy::StructuralIndex::new(large_input_str);
+
+    // The whole message, single threaded and split between threads.
+    let mut group = c.benchmark_group("large");
+    group.bench_function("serde", |b| {
+        b.iter(|| binance::serde::parse(black_box(large_input_str)))
+    });
+    group.bench_function("custom", |b| {
+        b.iter(|| binance::custom::parse(black_box(large_input_str)))
+    });
+    for threads in [1, 2, 4, 8] {
+        group.bench_function(format!("custom_parallel_{threads}"), |b| {
+            b.iter(|| binance::custom_parallel::parse_parallel(black_box(large_input_str), threads))
+        });
+    }
+    group.finish();
+
+    c.bench_function("custom_lazy_structural_index", |b| {
+        b.iter(|| binance::custom_lazy::StructuralIndex::new(black_box(large_input_str)))
+    });
+
+    // Random access is quadratic in the number of entries, keep the number of samples low.
+    let mut group = c.benchmark_group("custom_lazy_access");
+    group.sample_size(10);
+    for (name, indices, index) in [
+        ("sequential", &sequential, None),
+        ("random", &random, None),
+        ("sequential_indexed", &sequential, Some(&index)),
+        ("random_indexed", &random, Some(&index)),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let document = match index {
+                    Some(index) => binance::custom_lazy::Document::with_index(
+                        black_box(large_input_str),
+                        index,
+                    ),
+                    None => binance::custom_lazy::Document::new(black_box(large_input_str)),
+                };
+                let array = document.as_array().unwrap();
+                for &index in indices {
+                    black_box(
+                        array
+                            .get_index(index)
+                            .unwrap()
+                            .as_object()
+                            .unwrap()
+                            .get_key("symbol")
+                            .unwrap()
+                            .as_string()
+                            .unwrap()
+                            .get_value()
+                            .unwrap(),
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,238 @@
+//! Derive macro for `binance::custom_lazy::LazyExtract`.
+//!
+//! The generated code looks up every field in a single pass over the object, see
+//! `binance::custom_lazy::LazyExtract` for the supported attributes.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, GenericParam, Lifetime,
+    LifetimeParam, LitStr, Type,
+};
+
+#[proc_macro_derive(LazyExtract, attributes(lazy))]
+pub fn derive_lazy_extract(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How the names of the fields are turned into JSON keys.
+#[derive(Clone, Copy)]
+enum RenameAll {
+    CamelCase,
+    PascalCase,
+}
+
+impl RenameAll {
+    fn apply(self, name: &str) -> String {
+        let mut result = String::with_capacity(name.len());
+        let mut upper = matches!(self, RenameAll::PascalCase);
+        for c in name.chars() {
+            if c == '_' {
+                upper = true;
+            } else if upper {
+                result.extend(c.to_uppercase());
+                upper = false;
+            } else {
+                result.push(c);
+            }
+        }
+        result
+    }
+}
+
+#[derive(Default)]
+struct ContainerOptions {
+    rename_all: Option<RenameAll>,
+    deny_unknown_fields: bool,
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<String>,
+    default: bool,
+}
+
+fn container_options(attrs: &[Attribute]) -> syn::Result<ContainerOptions> {
+    let mut options = ContainerOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("lazy")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                let value: LitStr = meta.value()?.parse()?;
+                options.rename_all = Some(match value.value().as_str() {
+                    "camelCase" => RenameAll::CamelCase,
+                    "PascalCase" => RenameAll::PascalCase,
+                    _ => {
+                        return Err(Error::new(
+                            value.span(),
+                            "expected `camelCase` or `PascalCase`",
+                        ))
+                    }
+                });
+                Ok(())
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                options.deny_unknown_fields = true;
+                Ok(())
+            } else {
+                Err(meta
+                    .error("unsupported attribute, expected `rename_all` or `deny_unknown_fields`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("lazy")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let value: LitStr = meta.value()?.parse()?;
+                options.rename = Some(value.value());
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                options.default = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `rename` or `default`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+/// Returns true if the type is spelled as `Option<...>`, in which case a missing field is `None`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "LazyExtract can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "LazyExtract can only be derived for structs with named fields",
+        ));
+    };
+    if let Some(param) = input
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, GenericParam::Lifetime(_)))
+    {
+        return Err(Error::new_spanned(
+            param,
+            "LazyExtract does not support type or const parameters",
+        ));
+    }
+
+    let container = container_options(&input.attrs)?;
+
+    // The values borrow from the input with the first lifetime of the struct. Structs that don't
+    // borrow get a new lifetime in the impl.
+    let mut impl_generics = input.generics.clone();
+    let lifetime = match input.generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let lifetime = Lifetime::new("'__input", Span::call_site());
+            impl_generics
+                .params
+                .push(GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())));
+            lifetime
+        }
+    };
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut idents = Vec::new();
+    let mut missing = Vec::new();
+    for field in &fields.named {
+        let options = field_options(&field.attrs)?;
+        let ident = field.ident.as_ref().expect("named fields have an ident");
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let key = match (options.rename, container.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rename_all)) => rename_all.apply(name),
+            (None, None) => name.to_string(),
+        };
+        // Only the first field would ever be filled
+        if let Some(i) = keys.iter().position(|other| *other == key) {
+            return Err(Error::new_spanned(
+                field,
+                format!(
+                    "duplicate JSON key `{key}`, already used by field `{}`",
+                    idents[i]
+                ),
+            ));
+        }
+
+        missing.push(if options.default || is_option(&field.ty) {
+            quote!(::core::default::Default::default())
+        } else {
+            quote!(return ::core::result::Result::Err(
+                ::binance::custom_lazy::__private::missing_field(object, #key)
+            ))
+        });
+        keys.push(key);
+        idents.push(ident);
+    }
+
+    let name = &input.ident;
+    let deny_unknown_fields = container.deny_unknown_fields;
+    let nodes: Vec<_> = (0..idents.len())
+        .map(|i| syn::Ident::new(&format!("__node{i}"), Span::call_site()))
+        .collect();
+
+    Ok(quote! {
+        impl #impl_generics ::binance::custom_lazy::LazyExtract<#lifetime>
+            for #name #ty_generics #where_clause
+        {
+            fn extract(
+                object: &::binance::custom_lazy::Object<#lifetime>,
+            ) -> ::binance::custom_lazy::Result<Self> {
+                let [#(#nodes),*] = ::binance::custom_lazy::__private::extract_fields(
+                    object,
+                    &[#(#keys),*],
+                    #deny_unknown_fields,
+                )?;
+                ::core::result::Result::Ok(Self {
+                    #(#idents: match #nodes {
+                        ::core::option::Option::Some(node) => {
+                            ::binance::custom_lazy::FromNode::from_node(node)?
+                        }
+                        ::core::option::Option::None => #missing,
+                    },)*
+                })
+            }
+        }
+
+        impl #impl_generics ::binance::custom_lazy::FromNode<#lifetime>
+            for #name #ty_generics #where_clause
+        {
+            fn from_node(
+                node: ::binance::custom_lazy::GenericNode<#lifetime>,
+            ) -> ::binance::custom_lazy::Result<Self> {
+                <Self as ::binance::custom_lazy::LazyExtract<#lifetime>>::extract(
+                    &node.as_object()?,
+                )
+            }
+        }
+    })
+}
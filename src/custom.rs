@@ -1,13 +1,17 @@
 //! Bespoke json deserializer, because serde is great, but doesn't give you access to the raw input
 //! bytes of an object
 
-use std::mem::MaybeUninit;
+use crate::utils::{is_json_whitespace, LazyF64, LazyU64};
 
-use crate::utils::{LazyF64, LazyU64};
+mod edit;
+mod write;
 
-#[derive(thiserror::Error, Debug, Clone)]
-/// The error type used for the json module.
-pub enum Error {
+pub use edit::{EditError, EditableAst, EditableValue, PathSegment, Scalar};
+pub use write::{DisplayValue, Style};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+/// The kinds of error of the json module.
+pub enum ErrorKind {
     #[error("Unterminated string")]
     UnterminatedString,
     #[error("Invalid identifier")]
@@ -36,6 +40,78 @@ pub enum Error {
     UnknownField(String),
     #[error("Cannot get value out of non-string and non-integer type")]
     InvalidTokenType,
+    #[error("Invalid number")]
+    InvalidNumber,
+    #[error("The requested number is not an integer")]
+    NotAnInteger,
+    #[error("The number does not fit in the requested type")]
+    NumberOutOfRange,
+    #[error("Missing field {0}")]
+    MissingField(&'static str),
+    #[error("Duplicate field {0}")]
+    DuplicateField(&'static str),
+    #[error("Invalid JSON pointer: {0}")]
+    InvalidPointer(String),
+    #[error("The requested index {0} is not present in the array")]
+    IndexNotFound(usize),
+    #[error("Invalid array index: {0}")]
+    InvalidIndex(String),
+    #[error("Cannot look up a value inside a string, number, bool or null")]
+    NotAContainer,
+    #[error("Invalid UTF-8")]
+    InvalidUtf8,
+    #[error("Invalid escape sequence")]
+    InvalidEscape,
+    #[error("Unescaped control character in a string")]
+    ControlCharacter,
+    #[error("Too many nested objects and arrays")]
+    TooDeep,
+    #[error("Too many values in the document")]
+    TooManyValues,
+}
+
+impl ErrorKind {
+    pub(crate) fn at(self, offset: usize) -> Error {
+        Error { kind: self, offset }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{kind} at byte {offset}")]
+/// The error type used for the json module. Besides the kind of error, it records the offset in
+/// the input where the error was found.
+pub struct Error {
+    kind: ErrorKind,
+    offset: usize,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Offset in the input where the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the line and column where the error was found. `input` must be the data that
+    /// produced the error.
+    pub fn location(&self, input: &str) -> crate::diagnostics::Location {
+        crate::diagnostics::Location::from_offset(input, self.offset)
+    }
+
+    /// Returns a human readable description of the error, with its location and a snippet of
+    /// the input pointing at it. `input` must be the data that produced the error.
+    pub fn report(&self, input: &str) -> String {
+        crate::diagnostics::report(&self.kind, input, self.offset)
+    }
+
+    /// Moves the error by `base` bytes, for errors found in a slice of a larger input.
+    pub(crate) fn shifted(mut self, base: usize) -> Self {
+        self.offset += base;
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,7 +129,7 @@ enum TokenType {
     False,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Span(usize, usize);
 
 impl Span {
@@ -64,13 +140,6 @@ impl Span {
     const fn range(start: usize, end: usize) -> Self {
         Self(start, end)
     }
-
-    pub fn extend(&self, other: &Self) -> Self {
-        Self(
-            std::cmp::min(self.0, other.0),
-            std::cmp::max(self.1, other.1),
-        )
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -79,114 +148,205 @@ struct Token {
     ty: TokenType,
 }
 
-fn char_to_token_type(c: char) -> TokenType {
+fn char_to_token_type(c: u8) -> Option<TokenType> {
     match c {
-        '{' => TokenType::Lbrace,
-        '}' => TokenType::Rbrace,
-        '[' => TokenType::Lbracket,
-        ']' => TokenType::Rbracket,
-        ':' => TokenType::Colon,
-        ',' => TokenType::Comma,
-        _ => panic!("Single character does not match a token: {c}"),
+        b'{' => Some(TokenType::Lbrace),
+        b'}' => Some(TokenType::Rbrace),
+        b'[' => Some(TokenType::Lbracket),
+        b']' => Some(TokenType::Rbracket),
+        b':' => Some(TokenType::Colon),
+        b',' => Some(TokenType::Comma),
+        _ => None,
     }
 }
 
-fn ident_to_token_type(ident: &str) -> TokenType {
-    match ident {
-        "null" => TokenType::Null,
-        "true" => TokenType::True,
-        "false" => TokenType::False,
-        _ => panic!("Invalid identifier: {ident}"),
+/// States of the number lexer, named after the last part of the number that was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberState {
+    Start,
+    Minus,
+    Zero,
+    Integer,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl NumberState {
+    /// Returns the state after reading `c`, or `None` if `c` cannot continue the number.
+    fn next(self, c: u8) -> Option<Self> {
+        use NumberState::*;
+
+        match (self, c) {
+            (Start, b'-') => Some(Minus),
+            (Start | Minus, b'0') => Some(Zero),
+            (Start | Minus, b'1'..=b'9') | (Integer, b'0'..=b'9') => Some(Integer),
+            (Zero | Integer, b'.') => Some(Dot),
+            (Dot | Fraction, b'0'..=b'9') => Some(Fraction),
+            (Zero | Integer | Fraction, b'e' | b'E') => Some(Exponent),
+            (Exponent, b'+' | b'-') => Some(ExponentSign),
+            (Exponent | ExponentSign | ExponentDigits, b'0'..=b'9') => Some(ExponentDigits),
+            _ => None,
+        }
+    }
+
+    /// Whether a number can end in this state.
+    fn is_final(self) -> bool {
+        matches!(
+            self,
+            NumberState::Zero
+                | NumberState::Integer
+                | NumberState::Fraction
+                | NumberState::ExponentDigits
+        )
     }
 }
 
+/// Returns the index of the first byte that a string cannot contain as is: the closing quote, the
+/// start of an escape sequence, or a control character.
+///
+/// The bytes are checked 8 at a time, as the lanes of a `u64`. `x - 0x01..01 & !x & 0x80..80`
+/// sets the high bit of the lanes of `x` that are zero, plus maybe some lanes after them, so the
+/// lowest set bit is always the first match.
+#[inline(always)]
+fn find_string_end(bytes: &[u8]) -> Option<usize> {
+    const ONES: u64 = u64::from_le_bytes([0x01; 8]);
+    const HIGH: u64 = ONES * 0x80;
+    const QUOTES: u64 = ONES * b'"' as u64;
+    const BACKSLASHES: u64 = ONES * b'\\' as u64;
+
+    let mut i = 0;
+    while let Some(chunk) = bytes.get(i..i + 8) {
+        let lanes = u64::from_le_bytes(chunk.try_into().unwrap());
+        let quote = lanes ^ QUOTES;
+        let backslash = lanes ^ BACKSLASHES;
+        let found = (quote.wrapping_sub(ONES) & !quote
+            | backslash.wrapping_sub(ONES) & !backslash
+            | lanes.wrapping_sub(ONES * 0x20) & !lanes)
+            & HIGH;
+        if found != 0 {
+            return Some(i + found.trailing_zeros() as usize / 8);
+        }
+        i += 8;
+    }
+    bytes[i..]
+        .iter()
+        .position(|&c| c == b'"' || c == b'\\' || c < 0x20)
+        .map(|j| i + j)
+}
+
+/// Splits the input into tokens. Spans are byte offsets into the input, so they can be used to
+/// slice it directly.
+///
+/// The input is walked byte by byte. All the bytes that make up the structure of a JSON document
+/// are ASCII, and ASCII bytes never appear inside a multi-byte UTF-8 character, so every token
+/// starts and ends at a character boundary.
 struct TokenIter<'a> {
-    iter: std::iter::Peekable<std::iter::Enumerate<std::str::Chars<'a>>>,
+    bytes: &'a [u8],
+    pos: usize,
 }
 
 impl<'a> TokenIter<'a> {
     fn new(s: &'a str) -> Self {
         TokenIter {
-            iter: s.chars().enumerate().peekable(),
+            bytes: s.as_bytes(),
+            pos: 0,
         }
     }
 
-    fn next_string(&mut self) -> Result<Token, Error> {
-        let (start, _) = self
-            .iter
-            .next()
-            .expect("At least first character must be present");
+    #[inline(always)]
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
 
-        while let Some((i, c)) = self.iter.next() {
-            match c {
-                '\\' => {
-                    // escape sequence
-                    if self.iter.next().is_some_and(|(_, c)| c == 'u') {
-                        for _ in 0..4 {
-                            // consume 4 hex digits
-                            self.iter.next();
-                        }
-                    }
-                }
-                '"' => {
+    #[inline(always)]
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_json_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Lexes the string starting at the opening quote, which is at byte `start`. Escape
+    /// sequences are validated but not decoded.
+    #[inline(always)]
+    fn next_string(&mut self, start: usize) -> Result<Token, Error> {
+        self.pos += 1;
+
+        loop {
+            let rest = self.bytes.get(self.pos..).unwrap_or_default();
+            let Some(i) = find_string_end(rest) else {
+                return Err(ErrorKind::UnterminatedString.at(start));
+            };
+            let escape = self.pos + i;
+            self.pos = escape + 1;
+
+            match rest[i] {
+                b'"' => {
                     return Ok(Token {
-                        span: Span::range(start, i + 1),
+                        span: Span::range(start, self.pos),
                         ty: TokenType::String,
                     })
                 }
-                _ => {}
+                b'\\' => {}
+                _ => return Err(ErrorKind::ControlCharacter.at(escape)),
             }
-        }
 
-        Err(Error::UnterminatedString)
+            let len = match self.peek() {
+                Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => 1,
+                Some(b'u') => 5,
+                None => return Err(ErrorKind::UnterminatedString.at(start)),
+                Some(_) => return Err(ErrorKind::InvalidEscape.at(escape)),
+            };
+            let Some(sequence) = self.bytes.get(self.pos..self.pos + len) else {
+                return Err(ErrorKind::UnterminatedString.at(start));
+            };
+            if !sequence[1..].iter().all(u8::is_ascii_hexdigit) {
+                return Err(ErrorKind::InvalidEscape.at(escape));
+            }
+            self.pos += len;
+        }
     }
 
-    fn next_number(&mut self) -> Result<Token, Error> {
-        let (start, _) = self
-            .iter
-            .next()
-            .expect("At least first character must be present");
-
-        let mut end = start;
+    /// Lexes the number whose first character is at byte `start`, following the JSON grammar.
+    #[inline(always)]
+    fn next_number(&mut self, start: usize) -> Result<Token, Error> {
+        let mut state = NumberState::Start;
+
+        while let Some(c) = self.peek() {
+            let Some(next) = state.next(c) else {
+                break;
+            };
+            state = next;
+            self.pos += 1;
+        }
 
-        while let Some((i, c)) = self.iter.peek() {
-            match c {
-                '0'..='9' | '.' | 'e' | 'E' => {
-                    end = *i;
-                    self.iter.next();
-                }
-                _ => {
-                    break;
-                }
-            }
+        // A number must end in a complete state, and must not be directly followed by something
+        // that could continue it, like the second digit of `01` or the second dot of `1.2.3`.
+        let followed_by_number = self
+            .peek()
+            .is_some_and(|c| matches!(c, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-'));
+        if !state.is_final() || followed_by_number {
+            return Err(ErrorKind::InvalidNumber.at(self.pos));
         }
 
         Ok(Token {
-            span: Span::range(start, end + 1),
+            span: Span::range(start, self.pos),
             ty: TokenType::Number,
         })
     }
 
-    fn next_ident(&mut self, ident: &str) -> Result<Token, Error> {
-        let start = self
-            .iter
-            .peek()
-            .map(|(i, _)| *i)
-            .expect("At least first character must be present");
-
-        for expected in ident.chars() {
-            let matches = self
-                .iter
-                .next()
-                .is_some_and(|(_, actual)| actual == expected);
-            if !matches {
-                return Err(Error::InvalidIdentifier);
-            }
+    /// Lexes the identifier `ident`, which is expected to start at byte `start`.
+    fn next_ident(&mut self, start: usize, ident: &str, ty: TokenType) -> Result<Token, Error> {
+        if !self.bytes[start..].starts_with(ident.as_bytes()) {
+            return Err(ErrorKind::InvalidIdentifier.at(start));
         }
+        self.pos += ident.len();
 
         Ok(Token {
-            span: Span::range(start, start + ident.len()),
-            ty: ident_to_token_type(ident),
+            span: Span::range(start, self.pos),
+            ty,
         })
     }
 }
@@ -194,176 +354,441 @@ impl<'a> TokenIter<'a> {
 impl Iterator for TokenIter<'_> {
     type Item = Result<Token, Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let (i, c) = self.iter.peek().map(|(i, c)| (*i, *c))?;
-
-            match c {
-                ' ' | '\n' | '\r' | '\t' => {
-                    self.iter.next();
-                }
-                '{' | '}' | '[' | ']' | ':' | ',' => {
-                    self.iter.next();
-                    return Some(Ok(Token {
+        self.skip_whitespace();
+        let i = self.pos;
+
+        Some(match self.peek()? {
+            b'"' => self.next_string(i),
+            b'n' => self.next_ident(i, "null", TokenType::Null),
+            b't' => self.next_ident(i, "true", TokenType::True),
+            b'f' => self.next_ident(i, "false", TokenType::False),
+            b'0'..=b'9' | b'-' => self.next_number(i),
+            c => match char_to_token_type(c) {
+                Some(ty) => {
+                    self.pos += 1;
+                    Ok(Token {
                         span: Span::at(i),
-                        ty: char_to_token_type(c),
-                    }));
-                }
-                '"' => {
-                    return Some(self.next_string());
-                }
-                'n' => {
-                    return Some(self.next_ident("null"));
-                }
-                't' => {
-                    return Some(self.next_ident("true"));
-                }
-                'f' => {
-                    return Some(self.next_ident("false"));
-                }
-                c if c.is_ascii_digit() || c == '-' => {
-                    return Some(self.next_number());
-                }
-                _ => {
-                    return Some(Err(Error::InvalidToken));
+                        ty,
+                    })
                 }
-            }
-        }
+                None => Err(ErrorKind::InvalidToken.at(i)),
+            },
+        })
     }
 }
 
+/// Index of a node in the arena of an [`Ast`].
+type NodeId = u32;
+
+/// Marks the absence of a node, like the parent of the top-level value.
+const NO_NODE: NodeId = NodeId::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeType {
+    Object,
+    Array,
+    Number,
+    String,
+    Null,
+    Bool,
+}
+
+/// A value stored in the arena. Nodes are stored in document order, so the first child of a
+/// container, if any, is the node right after it. Each entry of an object is stored as its key,
+/// followed by its value.
 #[derive(Debug, Clone)]
-/// A JSON object as an AST element.
-pub struct ObjectAst {
+struct Node {
+    ty: NodeType,
     span: Span,
-    elems: Vec<(StringAst, ValueAst)>,
+    /// The container of this node, or `NO_NODE` for the top-level value.
+    parent: NodeId,
+    /// The next node in the same container, or `NO_NODE` for the last one. The next node of an
+    /// object key is its value.
+    next: NodeId,
+    /// Number of elements of an array, or number of entries of an object.
+    len: u32,
 }
 
-impl ObjectAst {
+/// A parsed JSON document.
+///
+/// All the values are stored in a single flat arena, and are accessed through lightweight handles
+/// (`ValueAst`, `ObjectAst`, ...) that borrow from it. Like the rest of the module, the handles
+/// only store offsets, so the input needs to be passed to get to the data.
+#[derive(Debug, Clone)]
+pub struct Ast {
+    nodes: Vec<Node>,
+}
+
+impl Ast {
+    /// Returns the top-level value of the document.
+    pub fn root(&self) -> ValueAst<'_> {
+        NodeRef {
+            nodes: &self.nodes,
+            id: 0,
+        }
+        .value()
+    }
+}
+
+/// A reference to a node of the arena, the building block of all the AST handles.
+#[derive(Clone, Copy)]
+struct NodeRef<'t> {
+    nodes: &'t [Node],
+    id: NodeId,
+}
+
+impl<'t> NodeRef<'t> {
+    fn node(&self) -> &'t Node {
+        &self.nodes[self.id as usize]
+    }
+
+    fn with_id(&self, id: NodeId) -> Option<Self> {
+        (id != NO_NODE).then_some(Self {
+            nodes: self.nodes,
+            id,
+        })
+    }
+
+    fn span(&self) -> &'t Span {
+        &self.node().span
+    }
+
+    fn offset(&self) -> usize {
+        self.span().0
+    }
+
+    fn raw<'a>(&self, s: &'a str) -> &'a str {
+        let span = self.span();
+        &s[span.0..span.1]
+    }
+
+    fn len(&self) -> usize {
+        self.node().len as usize
+    }
+
+    fn first_child(&self) -> Option<Self> {
+        if self.node().len == 0 {
+            None
+        } else {
+            self.with_id(self.id + 1)
+        }
+    }
+
+    fn next(&self) -> Option<Self> {
+        self.with_id(self.node().next)
+    }
+
+    fn parent(&self) -> Option<Self> {
+        self.with_id(self.node().parent)
+    }
+
+    fn value(self) -> ValueAst<'t> {
+        match self.node().ty {
+            NodeType::Object => ValueAst::Object(ObjectAst(self)),
+            NodeType::Array => ValueAst::Array(ArrayAst(self)),
+            NodeType::Number => ValueAst::Number(NumberAst(self)),
+            NodeType::String => ValueAst::String(StringAst(self)),
+            NodeType::Null => ValueAst::Null(NullAst(self)),
+            NodeType::Bool => ValueAst::Bool(BoolAst(self)),
+        }
+    }
+}
+
+impl std::fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = self.node();
+        f.debug_struct("Node")
+            .field("id", &self.id)
+            .field("ty", &node.ty)
+            .field("span", &node.span)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A JSON object as an AST element.
+pub struct ObjectAst<'t>(NodeRef<'t>);
+
+impl<'t> ObjectAst<'t> {
     /// Obtains the requested item by key, or returns KeyNotFound if the key was not found in the
     /// object.
-    pub fn get_item(&self, s: &str, key: &str) -> Result<&ValueAst, Error> {
-        for (k, v) in &self.elems {
+    pub fn get_item(&self, s: &str, key: &str) -> Result<ValueAst<'t>, Error> {
+        for (k, v) in self.iter() {
             if k.value(s) == key {
                 return Ok(v);
             }
         }
-        Err(Error::KeyNotFound)
+        Err(ErrorKind::KeyNotFound.at(self.0.offset()))
+    }
+
+    /// Like `get_item`, but compares the keys after decoding their escape sequences.
+    fn get_unescaped(&self, s: &str, key: &str) -> Result<ValueAst<'t>, Error> {
+        for (k, v) in self.iter() {
+            let found = crate::custom_lazy::unescaped_eq(k.value(s), k.0.offset() + 1, key)
+                .map_err(|error| ErrorKind::InvalidEscape.at(error.offset()))?;
+            if found {
+                return Ok(v);
+            }
+        }
+        Err(ErrorKind::KeyNotFound.at(self.0.offset()))
+    }
+
+    /// Returns an iterator over the entries of the object, in document order.
+    pub fn iter(&self) -> ObjectIter<'t> {
+        ObjectIter {
+            next: self.0.first_child(),
+        }
+    }
+
+    /// Returns the number of entries of the object.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.span.0;
-        let end = self.span.1;
-        &s[begin..end]
+        self.0.raw(s)
     }
 }
 
+/// Iterator over the entries of an [`ObjectAst`].
 #[derive(Debug, Clone)]
-/// A Json array as an AST element.
-pub struct ArrayAst {
-    span: Span,
-    elems: Vec<ValueAst>,
+pub struct ObjectIter<'t> {
+    next: Option<NodeRef<'t>>,
+}
+
+impl<'t> Iterator for ObjectIter<'t> {
+    type Item = (StringAst<'t>, ValueAst<'t>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.next?;
+        let value = key.next()?;
+        self.next = value.next();
+        Some((StringAst(key), value.value()))
+    }
 }
 
-impl ArrayAst {
+#[derive(Debug, Clone, Copy)]
+/// A Json array as an AST element.
+pub struct ArrayAst<'t>(NodeRef<'t>);
+
+impl<'t> ArrayAst<'t> {
+    /// Returns an iterator over the elements of the array.
+    pub fn iter(&self) -> ArrayIter<'t> {
+        ArrayIter {
+            next: self.0.first_child(),
+        }
+    }
+
+    /// Returns the number of elements of the array.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.span.0;
-        let end = self.span.1;
-        &s[begin..end]
+        self.0.raw(s)
     }
 }
 
-impl IntoIterator for ArrayAst {
-    type Item = ValueAst;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+impl<'t> IntoIterator for ArrayAst<'t> {
+    type Item = ValueAst<'t>;
+    type IntoIter = ArrayIter<'t>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.elems.into_iter()
+        self.iter()
     }
 }
 
+/// Iterator over the elements of an [`ArrayAst`].
 #[derive(Debug, Clone)]
+pub struct ArrayIter<'t> {
+    next: Option<NodeRef<'t>>,
+}
+
+impl<'t> Iterator for ArrayIter<'t> {
+    type Item = ValueAst<'t>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.next?;
+        self.next = element.next();
+        Some(element.value())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 /// A JSON number as an AST element
-pub struct NumberAst(Token);
+pub struct NumberAst<'t>(NodeRef<'t>);
 
-impl NumberAst {
+impl NumberAst<'_> {
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.0.span.0;
-        let end = self.0.span.1;
-        &s[begin..end]
+        self.0.raw(s)
+    }
+
+    /// Returns the number as an unsigned integer. Fails if the number has a fractional part or
+    /// an exponent, or if it does not fit in a `u64`.
+    pub fn as_u64(&self, s: &str) -> Result<u64, Error> {
+        let raw = self.integer_raw(s)?;
+        if let Some(digits) = raw.strip_prefix('-') {
+            // `-0` is valid JSON, any other negative number is out of range.
+            return if digits == "0" {
+                Ok(0)
+            } else {
+                Err(ErrorKind::NumberOutOfRange.at(self.0.offset()))
+            };
+        }
+        raw.parse()
+            .map_err(|_| ErrorKind::NumberOutOfRange.at(self.0.offset()))
+    }
+
+    /// Returns the number as a signed integer. Fails if the number has a fractional part or an
+    /// exponent, or if it does not fit in an `i64`.
+    pub fn as_i64(&self, s: &str) -> Result<i64, Error> {
+        self.integer_raw(s)?
+            .parse()
+            .map_err(|_| ErrorKind::NumberOutOfRange.at(self.0.offset()))
+    }
+
+    /// Returns the number as a floating point number. Fails if the number is too large to be
+    /// represented as a finite `f64`.
+    pub fn as_f64(&self, s: &str) -> Result<f64, Error> {
+        let value: f64 = self
+            .get_raw_string(s)
+            .parse()
+            .map_err(|_| ErrorKind::InvalidNumber.at(self.0.offset()))?;
+        if value.is_infinite() {
+            return Err(ErrorKind::NumberOutOfRange.at(self.0.offset()));
+        }
+        Ok(value)
+    }
+
+    fn integer_raw<'a>(&self, s: &'a str) -> Result<&'a str, Error> {
+        let raw = self.get_raw_string(s);
+        if raw.contains(['.', 'e', 'E']) {
+            return Err(ErrorKind::NotAnInteger.at(self.0.offset()));
+        }
+        Ok(raw)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 /// A JSON string as an AST element
-pub struct StringAst(Token);
+pub struct StringAst<'t>(NodeRef<'t>);
 
-impl StringAst {
+impl StringAst<'_> {
     /// Returns the inner string inside the json object.
     pub fn value<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.0.span.0;
-        let end = self.0.span.1;
-        &s[begin + 1..end - 1]
+        let span = self.0.span();
+        &s[span.0 + 1..span.1 - 1]
     }
 
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.0.span.0;
-        let end = self.0.span.1;
-        &s[begin..end]
+        self.0.raw(s)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 /// A Json null as an AST element.
-pub struct NullAst(Token);
+pub struct NullAst<'t>(NodeRef<'t>);
 
-impl NullAst {
+impl NullAst<'_> {
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.0.span.0;
-        let end = self.0.span.1;
-        &s[begin..end]
+        self.0.raw(s)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 /// A Json bool as an AST element.
-pub struct BoolAst(Token);
+pub struct BoolAst<'t>(NodeRef<'t>);
+
+impl BoolAst<'_> {
+    /// Returns the value of the bool.
+    pub fn value(&self, s: &str) -> bool {
+        self.get_raw_string(s) == "true"
+    }
 
-impl BoolAst {
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        let begin = self.0.span.0;
-        let end = self.0.span.1;
-        &s[begin..end]
+        self.0.raw(s)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 /// A Json Value as an AST element.
-pub enum ValueAst {
-    Object(ObjectAst),
-    Array(ArrayAst),
-    Number(NumberAst),
-    String(StringAst),
-    Null(NullAst),
-    Bool(BoolAst),
+pub enum ValueAst<'t> {
+    Object(ObjectAst<'t>),
+    Array(ArrayAst<'t>),
+    Number(NumberAst<'t>),
+    String(StringAst<'t>),
+    Null(NullAst<'t>),
+    Bool(BoolAst<'t>),
 }
 
-impl ValueAst {
+impl<'t> ValueAst<'t> {
+    fn node_ref(&self) -> NodeRef<'t> {
+        match self {
+            ValueAst::Object(o) => o.0,
+            ValueAst::Array(a) => a.0,
+            ValueAst::Null(n) => n.0,
+            ValueAst::Number(n) => n.0,
+            ValueAst::String(str) => str.0,
+            ValueAst::Bool(b) => b.0,
+        }
+    }
+
+    /// Offset in the input where this value starts.
+    pub fn offset(&self) -> usize {
+        self.node_ref().offset()
+    }
+
+    /// Returns the object or array that contains this value, or `None` for the top-level value.
+    pub fn parent(&self) -> Option<ValueAst<'t>> {
+        self.node_ref().parent().map(NodeRef::value)
+    }
+
     /// Returns the raw json data that makes up this object.
     pub fn get_raw_string<'a>(&self, s: &'a str) -> &'a str {
-        match self {
-            ValueAst::Object(o) => o.get_raw_string(s),
-            ValueAst::Array(a) => a.get_raw_string(s),
-            ValueAst::Null(n) => n.get_raw_string(s),
-            ValueAst::Number(n) => n.get_raw_string(s),
-            ValueAst::String(str) => str.get_raw_string(s),
-            ValueAst::Bool(b) => b.get_raw_string(s),
+        self.node_ref().raw(s)
+    }
+
+    /// Returns the value referenced by a JSON Pointer (RFC 6901), such as `/0/symbol`, relative to
+    /// this value. Unlike in [`ObjectAst::get_item`], keys are compared after decoding their
+    /// escape sequences, as the RFC requires.
+    pub fn pointer(&self, s: &str, pointer: &str) -> Result<ValueAst<'t>, Error> {
+        let Some(tokens) = crate::pointer::tokens(pointer) else {
+            return Err(ErrorKind::InvalidPointer(pointer.to_string()).at(self.offset()));
+        };
+
+        let mut value = *self;
+        for token in tokens {
+            value = match value {
+                ValueAst::Object(object) => object.get_unescaped(s, &token)?,
+                ValueAst::Array(array) => {
+                    let Some(index) = crate::pointer::array_index(&token) else {
+                        return Err(
+                            ErrorKind::InvalidIndex(token.into_owned()).at(array.0.offset())
+                        );
+                    };
+                    array
+                        .iter()
+                        .nth(index)
+                        .ok_or_else(|| ErrorKind::IndexNotFound(index).at(array.0.offset()))?
+                }
+                _ => return Err(ErrorKind::NotAContainer.at(value.offset())),
+            };
         }
+        Ok(value)
     }
 
     /// Returns the inner value in hte json for
@@ -372,112 +797,190 @@ impl ValueAst {
             ValueAst::Number(n) => n.get_raw_string(s),
             ValueAst::String(str) => str.value(s),
             _ => {
-                return Err(Error::InvalidTokenType);
+                return Err(ErrorKind::InvalidTokenType.at(self.offset()));
             }
         })
     }
 }
 
-/// Parses the given JSON data and returns an AST that represents the JSON data.
-pub fn parse_json(s: &str) -> Result<ValueAst, Error> {
-    fn parse_json_inner(iter: &mut std::iter::Peekable<TokenIter>) -> Result<ValueAst, Error> {
-        let Some(token) = iter.next() else {
-            return Err(Error::EmptyJson);
+/// Builds the arena of an [`Ast`]. The structural characters are read directly from the input,
+/// the lexer is only used for the scalar values.
+struct Parser<'a> {
+    lexer: TokenIter<'a>,
+    nodes: Vec<Node>,
+    /// Number of objects and arrays that are currently open.
+    depth: usize,
+}
+
+/// Maximum nesting of objects and arrays. Each level is a recursive call, so deeper documents
+/// would overflow the stack.
+pub(crate) const MAX_DEPTH: usize = 128;
+
+impl Parser<'_> {
+    /// Skips whitespace and returns the next byte without consuming it, or an error of the given
+    /// kind if the input ended.
+    #[inline(always)]
+    fn peek(&mut self, kind: ErrorKind) -> Result<u8, Error> {
+        self.lexer.skip_whitespace();
+        self.lexer
+            .peek()
+            .ok_or_else(|| kind.at(self.lexer.bytes.len()))
+    }
+
+    #[inline(always)]
+    fn push(&mut self, ty: NodeType, span: Span, parent: NodeId) -> Result<NodeId, Error> {
+        let id = match NodeId::try_from(self.nodes.len()) {
+            Ok(id) if id != NO_NODE => id,
+            _ => return Err(ErrorKind::TooManyValues.at(span.0)),
+        };
+        self.nodes.push(Node {
+            ty,
+            span,
+            parent,
+            next: NO_NODE,
+            len: 0,
+        });
+        Ok(id)
+    }
+
+    /// Makes `next` the next sibling of `previous`, unless `next` is the first child.
+    #[inline(always)]
+    fn link(&mut self, previous: NodeId, next: NodeId) {
+        if previous != NO_NODE {
+            self.nodes[previous as usize].next = next;
+        }
+    }
+
+    /// Parses the next value, including all its children. `kind` is the error returned if the
+    /// input ends before the value.
+    fn parse_value(&mut self, parent: NodeId, kind: ErrorKind) -> Result<NodeId, Error> {
+        let c = self.peek(kind)?;
+        let start = self.lexer.pos;
+        let token = match c {
+            b'{' => return self.parse_object(parent),
+            b'[' => return self.parse_array(parent),
+            b'"' => self.lexer.next_string(start)?,
+            b'n' => self.lexer.next_ident(start, "null", TokenType::Null)?,
+            b't' => self.lexer.next_ident(start, "true", TokenType::True)?,
+            b'f' => self.lexer.next_ident(start, "false", TokenType::False)?,
+            b'0'..=b'9' | b'-' => self.lexer.next_number(start)?,
+            c if char_to_token_type(c).is_some() => {
+                return Err(ErrorKind::UnexpectedToken.at(start))
+            }
+            _ => return Err(ErrorKind::InvalidToken.at(start)),
         };
+        let ty = match token.ty {
+            TokenType::String => NodeType::String,
+            TokenType::Number => NodeType::Number,
+            TokenType::Null => NodeType::Null,
+            _ => NodeType::Bool,
+        };
+        self.push(ty, token.span, parent)
+    }
 
-        let token = token?;
-
-        match token.ty {
-            TokenType::Number => Ok(ValueAst::Number(NumberAst(token))),
-            TokenType::String => Ok(ValueAst::String(StringAst(token))),
-            TokenType::Null => Ok(ValueAst::Null(NullAst(token))),
-            TokenType::True => Ok(ValueAst::Bool(BoolAst(token))),
-            TokenType::False => Ok(ValueAst::Bool(BoolAst(token))),
-            TokenType::Lbrace => {
-                // Handle object
-                let mut object = vec![];
-
-                let lbrace = token.span;
-
-                let next = iter.peek().ok_or(Error::UnterminatedJsonObject)?;
-                let next = next.clone()?;
-                if next.ty == TokenType::Rbrace {
-                    iter.next();
-                    let span = lbrace.extend(&next.span);
-                    return Ok(ValueAst::Object(ObjectAst {
-                        elems: object,
-                        span,
-                    }));
+    /// Enters an object or array that starts at `start`.
+    fn enter(&mut self, start: usize) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(ErrorKind::TooDeep.at(start));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn parse_object(&mut self, parent: NodeId) -> Result<NodeId, Error> {
+        let start = self.lexer.pos;
+        self.enter(start)?;
+        self.lexer.pos += 1;
+        let id = self.push(NodeType::Object, Span::at(start), parent)?;
+        let mut len = 0;
+        let mut last = NO_NODE;
+
+        let mut c = self.peek(ErrorKind::UnterminatedJsonObject)?;
+        if c != b'}' {
+            loop {
+                let key_start = self.lexer.pos;
+                if c != b'"' {
+                    return Err(ErrorKind::MalformedJsonObject.at(key_start));
                 }
+                let key = self.lexer.next_string(key_start)?;
+                let key = self.push(NodeType::String, key.span, id)?;
+                self.link(last, key);
 
-                while let Some(next) = iter.next() {
-                    let key = next?;
-                    if key.ty != TokenType::String {
-                        return Err(Error::MalformedJsonObject);
-                    };
+                if self.peek(ErrorKind::UnterminatedJsonObject)? != b':' {
+                    return Err(ErrorKind::MalformedJsonObject.at(self.lexer.pos));
+                }
+                self.lexer.pos += 1;
 
-                    if iter.next().ok_or(Error::UnterminatedJsonObject)??.ty != TokenType::Colon {
-                        return Err(Error::MalformedJsonObject);
-                    }
+                let value = self.parse_value(id, ErrorKind::UnterminatedJsonObject)?;
+                self.link(key, value);
+                last = value;
+                len += 1;
 
-                    let value = parse_json_inner(iter)?;
-                    object.push((StringAst(key), value));
-
-                    let next = iter.next().ok_or(Error::UnterminatedJsonObject)?;
-                    let next = next?;
-                    match next.ty {
-                        TokenType::Comma => {}
-                        TokenType::Rbrace => {
-                            let span = lbrace.extend(&next.span);
-                            return Ok(ValueAst::Object(ObjectAst {
-                                elems: object,
-                                span,
-                            }));
-                        }
-                        _ => {
-                            return Err(Error::MalformedJsonObject);
-                        }
+                match self.peek(ErrorKind::UnterminatedJsonObject)? {
+                    b',' => {
+                        self.lexer.pos += 1;
+                        c = self.peek(ErrorKind::UnterminatedJsonObject)?;
                     }
+                    b'}' => break,
+                    _ => return Err(ErrorKind::MalformedJsonObject.at(self.lexer.pos)),
                 }
-                Err(Error::UnterminatedJsonObject)
             }
-            TokenType::Lbracket => {
-                // Handle array
-                let mut array = vec![];
-                let lbracket = token.span;
-
-                let next = iter.peek().ok_or(Error::UnterminatedJsonArray)?;
-                let next = next.clone()?;
-                if next.ty == TokenType::Rbracket {
-                    iter.next();
-                    let span = lbracket.extend(&next.span);
-                    return Ok(ValueAst::Array(ArrayAst { elems: array, span }));
-                }
+        }
+        self.lexer.pos += 1;
+        self.depth -= 1;
 
-                loop {
-                    let value = parse_json_inner(iter)?;
-                    array.push(value);
-
-                    let next = iter.next().ok_or(Error::UnterminatedJsonArray)?;
-                    let next = next?;
-
-                    match next.ty {
-                        TokenType::Rbracket => {
-                            let span = lbracket.extend(&next.span);
-                            return Ok(ValueAst::Array(ArrayAst { span, elems: array }));
-                        }
-                        TokenType::Comma => {}
-                        _ => {
-                            return Err(Error::MalformedJsonArray);
-                        }
-                    }
+        let node = &mut self.nodes[id as usize];
+        node.span = Span::range(start, self.lexer.pos);
+        node.len = len;
+        Ok(id)
+    }
+
+    fn parse_array(&mut self, parent: NodeId) -> Result<NodeId, Error> {
+        let start = self.lexer.pos;
+        self.enter(start)?;
+        self.lexer.pos += 1;
+        let id = self.push(NodeType::Array, Span::at(start), parent)?;
+        let mut len = 0;
+        let mut last = NO_NODE;
+
+        if self.peek(ErrorKind::UnterminatedJsonArray)? != b']' {
+            loop {
+                let value = self.parse_value(id, ErrorKind::UnterminatedJsonArray)?;
+                self.link(last, value);
+                last = value;
+                len += 1;
+
+                match self.peek(ErrorKind::UnterminatedJsonArray)? {
+                    b',' => self.lexer.pos += 1,
+                    b']' => break,
+                    _ => return Err(ErrorKind::MalformedJsonArray.at(self.lexer.pos)),
                 }
             }
-            _ => Err(Error::UnexpectedToken),
         }
+        self.lexer.pos += 1;
+        self.depth -= 1;
+
+        let node = &mut self.nodes[id as usize];
+        node.span = Span::range(start, self.lexer.pos);
+        node.len = len;
+        Ok(id)
     }
-    let mut iter = TokenIter::new(s).peekable();
-    parse_json_inner(&mut iter)
+}
+
+/// Parses the given JSON data and returns an AST that represents the JSON data.
+pub fn parse_json(s: &str) -> Result<Ast, Error> {
+    let mut parser = Parser {
+        lexer: TokenIter::new(s),
+        // Rough estimate of the number of values, to avoid reallocating the arena in most cases.
+        nodes: Vec::with_capacity(s.len() / 8 + 1),
+        depth: 0,
+    };
+
+    parser.parse_value(NO_NODE, ErrorKind::EmptyJson)?;
+
+    Ok(Ast {
+        nodes: parser.nodes,
+    })
 }
 
 #[derive(Debug)]
@@ -502,97 +1005,130 @@ pub struct PriceChange24Hr<'a> {
     pub exercise_price: LazyF64<'a>,
 }
 
+/// JSON keys of the fields of [`PriceChange24Hr`], in declaration order.
+const PRICE_CHANGE_FIELDS: [&str; 18] = [
+    "symbol",
+    "priceChange",
+    "priceChangePercent",
+    "lastPrice",
+    "lastQty",
+    "open",
+    "high",
+    "low",
+    "volume",
+    "amount",
+    "bidPrice",
+    "askPrice",
+    "openTime",
+    "closeTime",
+    "firstTradeId",
+    "tradeCount",
+    "strikePrice",
+    "exercisePrice",
+];
+
+/// Collects the values of a price change entry, keeping track of which fields were already seen.
+#[derive(Default)]
+struct PriceChange24HrBuilder<'a> {
+    values: [Option<&'a str>; PRICE_CHANGE_FIELDS.len()],
+    /// Index of the field expected next. Binance always sends the fields in the same order, so
+    /// checking it first avoids searching for the key most of the time.
+    next: usize,
+}
+
+impl<'a> PriceChange24HrBuilder<'a> {
+    /// Stores the value of the field with the given key, which must be known and not set yet.
+    fn set(&mut self, key: &str, value: &'a str) -> Result<(), ErrorKind> {
+        let index = if PRICE_CHANGE_FIELDS.get(self.next) == Some(&key) {
+            self.next
+        } else {
+            let Some(index) = PRICE_CHANGE_FIELDS.iter().position(|field| *field == key) else {
+                return Err(ErrorKind::UnknownField(key.to_string()));
+            };
+            index
+        };
+        self.next = index + 1;
+        if self.values[index].replace(value).is_some() {
+            return Err(ErrorKind::DuplicateField(PRICE_CHANGE_FIELDS[index]));
+        }
+        Ok(())
+    }
+
+    /// Builds the entry, or returns the first field that was not set.
+    fn build(self) -> Result<PriceChange24Hr<'a>, ErrorKind> {
+        if let Some(index) = self.values.iter().position(Option::is_none) {
+            return Err(ErrorKind::MissingField(PRICE_CHANGE_FIELDS[index]));
+        }
+
+        // Indices follow the order of `PRICE_CHANGE_FIELDS`.
+        let values = self.values.map(Option::unwrap_or_default);
+
+        Ok(PriceChange24Hr {
+            symbol: values[0],
+            price_change: LazyF64(values[1]),
+            price_change_percent: LazyF64(values[2]),
+            last_price: LazyF64(values[3]),
+            last_qty: LazyF64(values[4]),
+            open: LazyF64(values[5]),
+            high: LazyF64(values[6]),
+            low: LazyF64(values[7]),
+            volume: LazyF64(values[8]),
+            amount: LazyF64(values[9]),
+            bid_price: LazyF64(values[10]),
+            ask_price: LazyF64(values[11]),
+            open_time: LazyU64(values[12]),
+            close_time: LazyU64(values[13]),
+            first_trade_id: LazyU64(values[14]),
+            trade_count: LazyU64(values[15]),
+            strike_price: LazyF64(values[16]),
+            exercise_price: LazyF64(values[17]),
+        })
+    }
+}
+
 pub fn parse_price_change_entry<'a>(
     s: &'a str,
     value: &ValueAst,
 ) -> Result<PriceChange24Hr<'a>, Error> {
-    let mut entry: MaybeUninit<PriceChange24Hr> = MaybeUninit::zeroed();
-    let entry_ptr = entry.as_mut_ptr();
-
-    match value {
-        ValueAst::Object(object) => {
-            for (k, v) in &object.elems {
-                let v = v.get_str_or_number_value(s)?;
-                match k.value(s) {
-                    "symbol" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).symbol).write(v);
-                    },
-                    "priceChange" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).price_change).write(LazyF64(v));
-                    },
-                    "priceChangePercent" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).price_change_percent).write(LazyF64(v));
-                    },
-                    "lastPrice" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).last_price).write(LazyF64(v));
-                    },
-                    "lastQty" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).last_qty).write(LazyF64(v));
-                    },
-                    "open" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).open).write(LazyF64(v));
-                    },
-                    "high" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).high).write(LazyF64(v));
-                    },
-                    "low" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).low).write(LazyF64(v));
-                    },
-                    "volume" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).volume).write(LazyF64(v));
-                    },
-                    "amount" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).amount).write(LazyF64(v));
-                    },
-                    "bidPrice" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).bid_price).write(LazyF64(v));
-                    },
-                    "askPrice" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).ask_price).write(LazyF64(v));
-                    },
-                    "openTime" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).open_time).write(LazyU64(v));
-                    },
-                    "closeTime" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).close_time).write(LazyU64(v));
-                    },
-                    "firstTradeId" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).first_trade_id).write(LazyU64(v));
-                    },
-                    "tradeCount" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).trade_count).write(LazyU64(v));
-                    },
-                    "strikePrice" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).strike_price).write(LazyF64(v));
-                    },
-                    "exercisePrice" => unsafe {
-                        std::ptr::addr_of_mut!((*entry_ptr).exercise_price).write(LazyF64(v));
-                    },
-                    k => {
-                        return Err(Error::UnknownField(k.to_string()));
-                    }
-                }
-            }
-
-            unsafe { Ok(entry.assume_init()) }
-        }
-        _ => Err(Error::NotAJsonArray),
+    let ValueAst::Object(object) = value else {
+        return Err(ErrorKind::NotAJsonObject.at(value.offset()));
+    };
+
+    let mut builder = PriceChange24HrBuilder::default();
+    for (key, v) in object.iter() {
+        let v = v.get_str_or_number_value(s)?;
+        builder
+            .set(key.value(s), v)
+            .map_err(|kind| kind.at(key.0.offset()))?;
     }
+    builder.build().map_err(|kind| kind.at(object.0.offset()))
 }
 
 /// Parses the data returned by the `GET /eapi/v1/ticker` endpoint
 pub fn parse(s: &str) -> Result<Vec<PriceChange24Hr>, Error> {
-    let data = parse_json(s)?;
+    let ast = parse_json(s)?;
+
+    // `parse_json` stops after the first value, but nothing may follow the array of entries
+    let root = ast.root();
+    let end = root.node_ref().span().1;
+    let mut lexer = TokenIter {
+        bytes: s.as_bytes(),
+        pos: end,
+    };
+    lexer.skip_whitespace();
+    if lexer.peek().is_some() {
+        return Err(ErrorKind::UnexpectedToken.at(lexer.pos));
+    }
 
-    match data {
+    match root {
         ValueAst::Array(array) => {
-            let mut result = Vec::with_capacity(array.elems.len());
-            for elem in array.elems {
+            let mut result = Vec::with_capacity(array.len());
+            for elem in array {
                 result.push(parse_price_change_entry(s, &elem)?);
             }
             Ok(result)
         }
-        _ => Err(Error::NotAJsonArray),
+        data => Err(ErrorKind::NotAJsonArray.at(data.offset())),
     }
 }
 
@@ -669,23 +1205,23 @@ mod test {
             }))
         ));
 
-        assert!(matches!(iter.next(), None));
+        assert!(iter.next().is_none());
+    }
+
+    /// Returns the type and span of the node behind a value.
+    fn node_of(value: &ValueAst) -> (NodeType, Span) {
+        let node = value.node_ref().node();
+        (node.ty, node.span.clone())
     }
 
     #[test]
     fn test_parsing_string() {
         let input = r#""Hello!", {}"#;
-        let value = parse_json(input).unwrap();
+        let ast = parse_json(input).unwrap();
 
-        assert!(matches!(
-            value,
-            ValueAst::String(StringAst(Token {
-                span: Span(0, 8),
-                ty: TokenType::String
-            }))
-        ));
+        assert_eq!(node_of(&ast.root()), (NodeType::String, Span(0, 8)));
 
-        match value {
+        match ast.root() {
             ValueAst::String(s) => {
                 assert_eq!(s.value(input), "Hello!");
             }
@@ -695,149 +1231,456 @@ mod test {
 
     #[test]
     fn test_parsing_number() {
-        let value = parse_json(r#"-1234.324,"#).unwrap();
+        let ast = parse_json(r#"-1234.324,"#).unwrap();
 
-        assert!(matches!(
-            value,
-            ValueAst::Number(NumberAst(Token {
-                span: Span(0, 9),
-                ty: TokenType::Number
-            }))
-        ));
+        assert_eq!(node_of(&ast.root()), (NodeType::Number, Span(0, 9)));
     }
 
     #[test]
     fn test_parsing_true() {
-        let value = parse_json(r#"true,"#).unwrap();
+        let input = r#"true,"#;
+        let ast = parse_json(input).unwrap();
 
-        assert!(matches!(
-            value,
-            ValueAst::Bool(BoolAst(Token {
-                span: Span(0, 4),
-                ty: TokenType::True
-            }))
-        ));
+        assert_eq!(node_of(&ast.root()), (NodeType::Bool, Span(0, 4)));
+        assert!(matches!(ast.root(), ValueAst::Bool(b) if b.value(input)));
     }
 
     #[test]
     fn test_parsing_false() {
-        let value = parse_json(r#"false,"#).unwrap();
+        let input = r#"false,"#;
+        let ast = parse_json(input).unwrap();
 
-        assert!(matches!(
-            value,
-            ValueAst::Bool(BoolAst(Token {
-                span: Span(0, 5),
-                ty: TokenType::False
-            }))
-        ));
+        assert_eq!(node_of(&ast.root()), (NodeType::Bool, Span(0, 5)));
+        assert!(matches!(ast.root(), ValueAst::Bool(b) if !b.value(input)));
     }
 
     #[test]
     fn test_parsing_null() {
-        let value = parse_json(r#"null,"#).unwrap();
+        let ast = parse_json(r#"null,"#).unwrap();
 
-        assert!(matches!(
-            value,
-            ValueAst::Null(NullAst(Token {
-                span: Span(0, 4),
-                ty: TokenType::Null
-            }))
-        ));
+        assert_eq!(node_of(&ast.root()), (NodeType::Null, Span(0, 4)));
     }
 
     #[test]
     fn test_parsing_array() {
-        let value = parse_json(r#"[null, true, false, "test", 123], {}"#).unwrap();
+        let ast = parse_json(r#"[null, true, false, "test", 123], {}"#).unwrap();
+        let value = ast.root();
 
         let ValueAst::Array(array) = value else {
             panic!("Value is not an array: {value:?}");
         };
 
-        assert_eq!(array.span.0, 0);
-        assert_eq!(array.span.1, 32);
+        assert_eq!(node_of(&value), (NodeType::Array, Span(0, 32)));
+        assert_eq!(array.len(), 5);
+
+        let elems: Vec<_> = array.iter().map(|elem| node_of(&elem)).collect();
+        assert_eq!(
+            elems,
+            [
+                (NodeType::Null, Span(1, 5)),
+                (NodeType::Bool, Span(7, 11)),
+                (NodeType::Bool, Span(13, 18)),
+                (NodeType::String, Span(20, 26)),
+                (NodeType::Number, Span(28, 31)),
+            ]
+        );
+
+        assert!(value.parent().is_none());
+        for elem in array {
+            let parent = elem.parent().unwrap();
+            assert_eq!(node_of(&parent), node_of(&value));
+        }
+    }
 
-        assert_eq!(array.elems.len(), 5);
+    #[test]
+    fn test_parsing_object() {
+        let ast = parse_json(r#"{"hi":null, "hello":true}"#).unwrap();
+        let value = ast.root();
 
-        assert!(matches!(
-            array.elems[0],
-            ValueAst::Null(NullAst(Token {
-                span: Span(1, 5),
-                ty: TokenType::Null
-            }))
-        ));
+        let ValueAst::Object(object) = value else {
+            panic!("Value is not an object: {value:?}");
+        };
 
-        assert!(matches!(
-            array.elems[1],
-            ValueAst::Bool(BoolAst(Token {
-                span: Span(7, 11),
-                ty: TokenType::True
-            }))
-        ));
+        assert_eq!(node_of(&value), (NodeType::Object, Span(0, 25)));
+        assert_eq!(object.len(), 2);
+
+        let elems: Vec<_> = object
+            .iter()
+            .map(|(key, value)| (key.0.span().clone(), node_of(&value)))
+            .collect();
+        assert_eq!(
+            elems,
+            [
+                (Span(1, 5), (NodeType::Null, Span(6, 10))),
+                (Span(12, 19), (NodeType::Bool, Span(20, 24))),
+            ]
+        );
+    }
 
-        assert!(matches!(
-            array.elems[2],
-            ValueAst::Bool(BoolAst(Token {
-                span: Span(13, 18),
-                ty: TokenType::False
-            }))
-        ));
+    #[test]
+    fn test_arena_layout() {
+        let ast = parse_json(r#"{"a": [1, {}], "b": [], "c": {"d": 2}}"#).unwrap();
+
+        let layout: Vec<_> = ast
+            .nodes
+            .iter()
+            .map(|node| (node.ty, node.parent, node.next, node.len))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                (NodeType::Object, NO_NODE, NO_NODE, 3),
+                (NodeType::String, 0, 2, 0),
+                (NodeType::Array, 0, 5, 2),
+                (NodeType::Number, 2, 4, 0),
+                (NodeType::Object, 2, NO_NODE, 0),
+                (NodeType::String, 0, 6, 0),
+                (NodeType::Array, 0, 7, 0),
+                (NodeType::String, 0, 8, 0),
+                (NodeType::Object, 0, NO_NODE, 1),
+                (NodeType::String, 8, 10, 0),
+                (NodeType::Number, 8, NO_NODE, 0),
+            ]
+        );
+    }
 
-        assert!(matches!(
-            array.elems[3],
-            ValueAst::String(StringAst(Token {
-                span: Span(20, 26),
-                ty: TokenType::String
-            }))
-        ));
+    #[test]
+    fn error_positions() {
+        let input = "[\n  {\"a\": 1},\n  {\"b\": tru}\n]";
+        let error = parse_json(input).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidIdentifier);
+        assert_eq!(error.offset(), input.find("tru").unwrap());
+        assert_eq!(
+            error.location(input),
+            crate::diagnostics::Location { line: 3, column: 9 }
+        );
+        assert_eq!(
+            error.report(input),
+            "Invalid identifier at line 3, column 9\n  {\"b\": tru}\n        ^"
+        );
+
+        let input = r#"[{"a": 1}, {"b" 2}]"#;
+        let error = parse_json(input).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::MalformedJsonObject);
+        assert_eq!(error.offset(), 16);
+
+        let input = r#"[1, 2"#;
+        let error = parse_json(input).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnterminatedJsonArray);
+        assert_eq!(error.offset(), input.len());
+
+        let input = r#"[{"symbol": "A", "foo": "1"}]"#;
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnknownField("foo".to_string()));
+        assert_eq!(error.offset(), input.find("\"foo").unwrap());
+    }
 
-        assert!(matches!(
-            array.elems[4],
-            ValueAst::Number(NumberAst(Token {
-                span: Span(28, 31),
-                ty: TokenType::Number
-            }))
-        ));
+    #[test]
+    fn test_multibyte_spans() {
+        let input = r#"{"naïve": "日本語", "ключ": [1, "€"], "🙂": null}"#;
+        let ast = parse_json(input).unwrap();
+        let ValueAst::Object(object) = ast.root() else {
+            panic!("Value is not an object");
+        };
+        assert_eq!(object.get_raw_string(input), input);
+
+        let keys: Vec<_> = object.iter().map(|(k, _)| k.value(input)).collect();
+        assert_eq!(keys, ["naïve", "ключ", "🙂"]);
+
+        let value = object.get_item(input, "naïve").unwrap();
+        assert_eq!(value.get_str_or_number_value(input).unwrap(), "日本語");
+
+        let ValueAst::Array(array) = object.get_item(input, "ключ").unwrap() else {
+            panic!("Value is not an array");
+        };
+        assert_eq!(array.get_raw_string(input), r#"[1, "€"]"#);
+        assert_eq!(array.iter().nth(1).unwrap().get_raw_string(input), r#""€""#);
+
+        let value = object.get_item(input, "🙂").unwrap();
+        assert_eq!(value.get_raw_string(input), "null");
     }
 
     #[test]
-    fn test_parsing_object() {
-        let value = parse_json(r#"{"hi":null, "hello":true}"#).unwrap();
+    fn test_multibyte_error_offset() {
+        let input = r#"["é", x]"#;
+        let error = parse_json(input).unwrap_err();
+        assert_eq!(error.offset(), input.find('x').unwrap());
+        assert_eq!(
+            error.location(input),
+            crate::diagnostics::Location { line: 1, column: 7 }
+        );
+    }
 
-        let ValueAst::Object(object) = value else {
-            panic!("Value is not an object: {value:?}");
+    #[test]
+    fn test_parse_entry() {
+        let input = std::fs::read_to_string("single.txt").unwrap();
+        let entries = parse(&input).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].symbol, "BTC-241206-115000-C");
+        assert_eq!(entries[0].price_change.0, "-45");
+        assert_eq!(entries[0].trade_count.0, "77");
+        assert_eq!(entries[0].exercise_price.0, "96638.25170213");
+    }
+
+    #[test]
+    fn test_parse_entry_missing_and_duplicate_fields() {
+        let input = std::fs::read_to_string("single.txt").unwrap();
+
+        let missing = input.replace(r#""lastQty":"0.1","#, "");
+        let error = parse(&missing).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::MissingField("lastQty"));
+        assert_eq!(error.offset(), 1);
+
+        let duplicate = input.replace(r#""low":"50","#, r#""low":"50","high":"1","#);
+        let error = parse(&duplicate).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DuplicateField("high"));
+        assert_eq!(error.offset(), duplicate.rfind(r#""high""#).unwrap());
+
+        let error = parse(r#"[{}]"#).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::MissingField("symbol"));
+    }
+
+    #[test]
+    fn test_find_string_end() {
+        let naive = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .position(|&c| c == b'"' || c == b'\\' || c < 0x20)
         };
+        for filler in ["a", "é", "\u{7f}", "\u{10ffff}", " "] {
+            for len in 0..20 {
+                let run = filler.repeat(len);
+                assert_eq!(find_string_end(run.as_bytes()), naive(run.as_bytes()));
+                for special in ["\"", "\\", "\n", "\0", "\u{1f}"] {
+                    let input = format!("{run}{special}{run}\"");
+                    let expected = naive(input.as_bytes());
+                    assert_eq!(find_string_end(input.as_bytes()), expected, "{input:?}");
+                }
+            }
+        }
+    }
 
-        assert_eq!(object.span.0, 0);
-        assert_eq!(object.span.1, 25);
+    #[test]
+    fn test_trailing_data() {
+        let input = std::fs::read_to_string("single.txt").unwrap();
+        assert!(parse(&format!("{input} \n")).is_ok());
+
+        let trailing = format!("{input} x");
+        let error = parse(&trailing).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedToken);
+        assert_eq!(error.offset(), trailing.len() - 1);
+        assert!(parse(&format!("{}]", input.trim_end())).is_err());
+    }
 
-        assert_eq!(object.elems.len(), 2);
+    /// Truncated and malformed inputs. None of them is valid JSON, so they must all fail without
+    /// panicking.
+    const MALFORMED_INPUTS: &[&str] = &[
+        "",
+        " ",
+        "[",
+        "{",
+        "]",
+        "}",
+        ":",
+        ",",
+        "[{\"a\":nul",
+        "[{\"a\":tru",
+        "[{\"a\":fals",
+        "[{\"a\":nulx}]",
+        "[{\"a\"",
+        "[{\"a\":",
+        "[{\"a\":1",
+        "[{\"a\":1,",
+        "[{\"a\" 1}]",
+        "[{1:1}]",
+        "[{\"a\":1]",
+        "[1}",
+        "[1 2]",
+        "[\"abc",
+        "[\"abc\\",
+        "[\"\\u12",
+        "[-",
+        "[@]",
+        "[\"é",
+        "é",
+        "{\"a\":\"b\" ,, }",
+        "[\"\\x\"]",
+        "[\"\\u12G4\"]",
+        "[\"\\\"]",
+        "[\"a\nb\"]",
+        "[\"\t\"]",
+        "[{\"a\u{1}\":1}]",
+    ];
 
-        assert!(matches!(
-            object.elems[0],
-            (
-                StringAst(Token {
-                    span: Span(1, 5),
-                    ty: TokenType::String
-                }),
-                ValueAst::Null(NullAst(Token {
-                    span: Span(6, 10),
-                    ty: TokenType::Null
-                }))
-            )
-        ));
+    #[test]
+    fn test_malformed_inputs() {
+        let deep = ["[".repeat(200000), "[{\"a\":".repeat(100000)];
+        let inputs = MALFORMED_INPUTS.iter().copied();
+        for input in inputs.chain(deep.iter().map(String::as_str)) {
+            assert!(parse_json(input).is_err(), "{input:?} should not parse");
+            assert!(parse(input).is_err(), "{input:?} should not parse");
+        }
+    }
 
-        assert!(matches!(
-            object.elems[1],
-            (
-                StringAst(Token {
-                    span: Span(12, 19),
-                    ty: TokenType::String
-                }),
-                ValueAst::Bool(BoolAst(Token {
-                    span: Span(20, 24),
-                    ty: TokenType::True
-                }))
-            )
-        ));
+    #[test]
+    fn test_string_errors() {
+        for (input, kind, offset) in [
+            (r#"["\x"]"#, ErrorKind::InvalidEscape, 2),
+            (r#"["ab\u12G4"]"#, ErrorKind::InvalidEscape, 4),
+            (r#"["\u12"]"#, ErrorKind::InvalidEscape, 2),
+            (r#"["\u12"#, ErrorKind::UnterminatedString, 1),
+            ("[\"a\nb\"]", ErrorKind::ControlCharacter, 3),
+        ] {
+            let error = parse_json(input).err();
+            let error = error.map(|e| (e.kind().clone(), e.offset()));
+            assert_eq!(error, Some((kind, offset)), "{input:?}");
+        }
+
+        let input = r#"["\"\\\/\b\f\n\r\t\u00e9\uD834\uDD1E", "\u007f"]"#;
+        assert!(parse_json(input).is_ok());
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse_json(&nested(MAX_DEPTH)).is_ok());
+
+        let error = parse_json(&nested(MAX_DEPTH + 1)).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::TooDeep);
+        assert_eq!(error.offset(), MAX_DEPTH);
+    }
+
+    #[test]
+    fn test_truncated_inputs() {
+        let input = std::fs::read_to_string("single.txt").unwrap();
+        let input = input.trim_end();
+        for (end, _) in input.char_indices() {
+            let error = parse(&input[..end]).err();
+            assert!(error.is_some(), "{:?} should not parse", &input[..end]);
+        }
+        assert!(parse(input).is_ok());
+    }
+
+    #[test]
+    fn test_number_grammar() {
+        for input in [
+            "0", "-0", "7", "-12", "0.5", "-0.25", "10.0", "1e5", "1E5", "1e+5", "1e-5", "1.5e-10",
+            "-0E+0",
+        ] {
+            let mut iter = TokenIter::new(input);
+            let token = iter.next().unwrap().unwrap();
+            assert_eq!(token.ty, TokenType::Number, "{input}");
+            assert_eq!(&input[token.span.0..token.span.1], input);
+            assert!(iter.next().is_none());
+        }
+
+        for (input, offset) in [
+            ("-", 1),
+            ("01", 1),
+            ("-01", 2),
+            ("1.", 2),
+            ("1..2e", 2),
+            (".5", 0),
+            ("1e", 2),
+            ("1e+", 3),
+            ("1.2.3", 3),
+            ("1e5e", 3),
+            ("--1", 1),
+            ("+1", 0),
+        ] {
+            let error = TokenIter::new(input).next().unwrap().unwrap_err();
+            let expected = if offset == 0 {
+                ErrorKind::InvalidToken
+            } else {
+                ErrorKind::InvalidNumber
+            };
+            assert_eq!(error.kind(), &expected, "{input}");
+            assert_eq!(error.offset(), offset, "{input}");
+        }
+
+        let ast = parse_json("[1e-5, -0.5, 2]").unwrap();
+        assert_eq!(
+            ast.root().get_raw_string("[1e-5, -0.5, 2]"),
+            "[1e-5, -0.5, 2]"
+        );
+    }
+
+    #[test]
+    fn test_number_accessors() {
+        let input = r#"[42, -7, 1.5, 1e3, 18446744073709551616, -0, 1e400]"#;
+        let ast = parse_json(input).unwrap();
+        let ValueAst::Array(array) = ast.root() else {
+            panic!("Value is not an array");
+        };
+        let numbers: Vec<_> = array
+            .into_iter()
+            .map(|value| match value {
+                ValueAst::Number(number) => number,
+                _ => panic!("Value is not a number: {value:?}"),
+            })
+            .collect();
+
+        assert_eq!(numbers[0].as_u64(input).unwrap(), 42);
+        assert_eq!(numbers[0].as_i64(input).unwrap(), 42);
+        assert_eq!(numbers[0].as_f64(input).unwrap(), 42.0);
+
+        assert_eq!(numbers[1].as_i64(input).unwrap(), -7);
+        assert_eq!(
+            numbers[1].as_u64(input).unwrap_err().kind(),
+            &ErrorKind::NumberOutOfRange
+        );
+
+        assert_eq!(numbers[2].as_f64(input).unwrap(), 1.5);
+        assert_eq!(
+            numbers[2].as_i64(input).unwrap_err().kind(),
+            &ErrorKind::NotAnInteger
+        );
+        assert_eq!(numbers[3].as_f64(input).unwrap(), 1000.0);
+        assert_eq!(
+            numbers[3].as_u64(input).unwrap_err().kind(),
+            &ErrorKind::NotAnInteger
+        );
+
+        assert_eq!(
+            numbers[4].as_u64(input).unwrap_err().kind(),
+            &ErrorKind::NumberOutOfRange
+        );
+        assert_eq!(numbers[4].as_f64(input).unwrap(), 18446744073709551616.0);
+
+        assert_eq!(numbers[5].as_u64(input).unwrap(), 0);
+        assert_eq!(
+            numbers[6].as_f64(input).unwrap_err().kind(),
+            &ErrorKind::NumberOutOfRange
+        );
+    }
+
+    #[test]
+    fn test_pointer() {
+        let input = r#"[{"symbol": "A", "a/b": [1, {"m~n": null}], "": 2}, 3]"#;
+        let ast = parse_json(input).unwrap();
+        let root = ast.root();
+        let raw = |pointer| {
+            root.pointer(input, pointer)
+                .map(|v| v.get_raw_string(input))
+        };
+
+        assert_eq!(raw("").unwrap(), input);
+        assert_eq!(raw("/0/symbol").unwrap(), r#""A""#);
+        assert_eq!(raw("/0/a~1b/1/m~0n").unwrap(), "null");
+        assert_eq!(raw("/0/").unwrap(), "2");
+        assert_eq!(raw("/1").unwrap(), "3");
+
+        let kind = |pointer| raw(pointer).unwrap_err().kind().clone();
+        assert_eq!(kind("0"), ErrorKind::InvalidPointer("0".to_string()));
+        assert_eq!(kind("/~2"), ErrorKind::InvalidPointer("/~2".to_string()));
+        assert_eq!(kind("/2"), ErrorKind::IndexNotFound(2));
+        assert_eq!(kind("/01"), ErrorKind::InvalidIndex("01".to_string()));
+        assert_eq!(kind("/-"), ErrorKind::InvalidIndex("-".to_string()));
+        assert_eq!(kind("/0/missing"), ErrorKind::KeyNotFound);
+        assert_eq!(kind("/1/0"), ErrorKind::NotAContainer);
+        assert_eq!(
+            raw("/0/symbol/x").unwrap_err().offset(),
+            input.find("\"A").unwrap()
+        );
     }
 }
@@ -0,0 +1,528 @@
+//! An editable layer over the AST.
+//!
+//! An `EditableAst` starts as a thin wrapper around a parsed value. Objects and arrays are only
+//! turned into owned values when something inside them is edited, and only one level at a time:
+//! their children stay as handles into the original AST. Unmodified sub-trees keep borrowing from
+//! the input, and are written back verbatim.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+
+use super::write::{write_container, write_io, write_key, write_value, Style};
+use super::{TokenIter, ValueAst};
+
+/// The error type of the edit operations.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    #[error("The path must not be empty")]
+    EmptyPath,
+    #[error("Key was not found: {0}")]
+    KeyNotFound(String),
+    #[error("Key already exists: {0}")]
+    KeyExists(String),
+    #[error("Index {index} is out of bounds for an array of length {len}")]
+    IndexOutOfBounds { index: usize, len: usize },
+    #[error("Cannot look up key {0} in a value that is not an object")]
+    NotAnObject(String),
+    #[error("Cannot look up index {0} in a value that is not an array")]
+    NotAnArray(usize),
+    #[error("Invalid JSON number: {0}")]
+    InvalidNumber(String),
+}
+
+/// A step of a path into a document: a key of an object or an index of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'p> {
+    Key(&'p str),
+    Index(usize),
+}
+
+impl<'p> From<&'p str> for PathSegment<'p> {
+    fn from(key: &'p str) -> Self {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment<'_> {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// A value of an `EditableAst`.
+#[derive(Debug, Clone)]
+pub enum EditableValue<'a> {
+    /// A value of the parsed AST that was not modified.
+    Original(ValueAst<'a>),
+    /// An object with edited entries. Keys are stored as their raw JSON text, without the quotes.
+    Object(Vec<(Cow<'a, str>, EditableValue<'a>)>),
+    /// An array with edited elements.
+    Array(Vec<EditableValue<'a>>),
+    /// A new string, number, bool or null.
+    Scalar(Scalar<'a>),
+}
+
+/// The JSON text of a scalar. It can only be created by the constructors of `EditableValue`,
+/// which make sure that it is valid JSON.
+#[derive(Debug, Clone)]
+pub struct Scalar<'a>(Cow<'a, str>);
+
+impl Scalar<'_> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> EditableValue<'a> {
+    /// Creates a JSON string with the given contents, escaping them as needed.
+    pub fn string(value: &str) -> Self {
+        EditableValue::Scalar(Scalar(Cow::Owned(format!("\"{}\"", escape(value)))))
+    }
+
+    /// Creates a JSON number written exactly as `text`, e.g. to keep all the digits of a price.
+    /// Fails if `text` is not a valid JSON number.
+    pub fn number(text: &'a str) -> Result<Self, EditError> {
+        let mut lexer = TokenIter::new(text);
+        match lexer.next_number(0) {
+            Ok(token) if token.span.1 == text.len() => {
+                Ok(EditableValue::Scalar(Scalar(Cow::Borrowed(text))))
+            }
+            _ => Err(EditError::InvalidNumber(text.to_string())),
+        }
+    }
+
+    pub fn bool(value: bool) -> Self {
+        EditableValue::Scalar(Scalar(Cow::Borrowed(if value { "true" } else { "false" })))
+    }
+
+    pub fn null() -> Self {
+        EditableValue::Scalar(Scalar(Cow::Borrowed("null")))
+    }
+
+    /// Turns an original object or array into an owned one, so its children can be edited.
+    fn materialize(&mut self, s: &'a str) {
+        match self {
+            EditableValue::Original(ValueAst::Object(object)) => {
+                *self = EditableValue::Object(
+                    object
+                        .iter()
+                        .map(|(k, v)| (Cow::Borrowed(k.value(s)), EditableValue::Original(v)))
+                        .collect(),
+                );
+            }
+            EditableValue::Original(ValueAst::Array(array)) => {
+                *self = EditableValue::Array(array.iter().map(EditableValue::Original).collect());
+            }
+            _ => {}
+        }
+    }
+
+    fn child_mut(
+        &mut self,
+        s: &'a str,
+        segment: PathSegment,
+    ) -> Result<&mut EditableValue<'a>, EditError> {
+        self.materialize(s);
+        match (self, segment) {
+            (EditableValue::Object(entries), PathSegment::Key(key)) => {
+                match position(entries, key) {
+                    Some(i) => Ok(&mut entries[i].1),
+                    None => Err(EditError::KeyNotFound(key.to_string())),
+                }
+            }
+            (EditableValue::Array(values), PathSegment::Index(index)) => {
+                let len = values.len();
+                values
+                    .get_mut(index)
+                    .ok_or(EditError::IndexOutOfBounds { index, len })
+            }
+            (_, segment) => Err(segment_mismatch(segment)),
+        }
+    }
+
+    fn write<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        s: &str,
+        style: Style,
+        depth: usize,
+    ) -> fmt::Result {
+        match self {
+            EditableValue::Original(value) => write_value(out, s, value, style, depth),
+            EditableValue::Object(entries) => {
+                write_container(out, style, depth, ('{', '}'), entries, |out, (k, v)| {
+                    write_key(out, k, style)?;
+                    v.write(out, s, style, depth + 1)
+                })
+            }
+            EditableValue::Array(values) => {
+                write_container(out, style, depth, ('[', ']'), values, |out, v| {
+                    v.write(out, s, style, depth + 1)
+                })
+            }
+            EditableValue::Scalar(scalar) => out.write_str(scalar.as_str()),
+        }
+    }
+}
+
+impl From<bool> for EditableValue<'_> {
+    fn from(value: bool) -> Self {
+        EditableValue::bool(value)
+    }
+}
+
+impl From<i64> for EditableValue<'_> {
+    fn from(value: i64) -> Self {
+        EditableValue::Scalar(Scalar(Cow::Owned(value.to_string())))
+    }
+}
+
+impl From<u64> for EditableValue<'_> {
+    fn from(value: u64) -> Self {
+        EditableValue::Scalar(Scalar(Cow::Owned(value.to_string())))
+    }
+}
+
+/// Non finite numbers can't be represented in JSON, they become `null`.
+impl From<f64> for EditableValue<'_> {
+    fn from(value: f64) -> Self {
+        if value.is_finite() {
+            EditableValue::Scalar(Scalar(Cow::Owned(value.to_string())))
+        } else {
+            EditableValue::null()
+        }
+    }
+}
+
+/// A document that can be edited with paths of keys and indices.
+///
+/// Keys are compared with their escaped text: a key of the input that uses an escape sequence
+/// where it didn't need one (e.g. `\u0041`) is not found.
+#[derive(Debug, Clone)]
+pub struct EditableAst<'a> {
+    s: &'a str,
+    root: EditableValue<'a>,
+}
+
+impl<'a> EditableAst<'a> {
+    /// Wraps the given value. `s` must be the input the AST was parsed from.
+    pub fn new(s: &'a str, root: ValueAst<'a>) -> Self {
+        Self {
+            s,
+            root: EditableValue::Original(root),
+        }
+    }
+
+    pub fn root(&self) -> &EditableValue<'a> {
+        &self.root
+    }
+
+    /// Replaces the value at `path`, returning the previous one. A missing key is added at the
+    /// end of its object, but indices must already exist. An empty path replaces the root.
+    pub fn set(
+        &mut self,
+        path: &[PathSegment],
+        value: EditableValue<'a>,
+    ) -> Result<Option<EditableValue<'a>>, EditError> {
+        let Some((parent, last)) = self.parent_mut(path)? else {
+            return Ok(Some(std::mem::replace(&mut self.root, value)));
+        };
+
+        match (parent, last) {
+            (EditableValue::Object(entries), PathSegment::Key(key)) => {
+                match position(entries, key) {
+                    Some(i) => Ok(Some(std::mem::replace(&mut entries[i].1, value))),
+                    None => {
+                        entries.push((Cow::Owned(escape(key).into_owned()), value));
+                        Ok(None)
+                    }
+                }
+            }
+            (EditableValue::Array(values), PathSegment::Index(index)) => {
+                let len = values.len();
+                let v = values
+                    .get_mut(index)
+                    .ok_or(EditError::IndexOutOfBounds { index, len })?;
+                Ok(Some(std::mem::replace(v, value)))
+            }
+            (_, segment) => Err(segment_mismatch(segment)),
+        }
+    }
+
+    /// Inserts a value at `path`. A key is added at the end of its object and must not exist
+    /// yet. An index shifts the following elements of its array, and may be the length of the
+    /// array to append to it.
+    pub fn insert(
+        &mut self,
+        path: &[PathSegment],
+        value: EditableValue<'a>,
+    ) -> Result<(), EditError> {
+        let (parent, last) = self.parent_mut(path)?.ok_or(EditError::EmptyPath)?;
+
+        match (parent, last) {
+            (EditableValue::Object(entries), PathSegment::Key(key)) => {
+                if position(entries, key).is_some() {
+                    return Err(EditError::KeyExists(key.to_string()));
+                }
+                entries.push((Cow::Owned(escape(key).into_owned()), value));
+                Ok(())
+            }
+            (EditableValue::Array(values), PathSegment::Index(index)) => {
+                if index > values.len() {
+                    let len = values.len();
+                    return Err(EditError::IndexOutOfBounds { index, len });
+                }
+                values.insert(index, value);
+                Ok(())
+            }
+            (_, segment) => Err(segment_mismatch(segment)),
+        }
+    }
+
+    /// Removes the value at `path` and returns it. The following elements of an array are
+    /// shifted, and the order of the remaining entries of an object is preserved.
+    pub fn remove(&mut self, path: &[PathSegment]) -> Result<EditableValue<'a>, EditError> {
+        let (parent, last) = self.parent_mut(path)?.ok_or(EditError::EmptyPath)?;
+
+        match (parent, last) {
+            (EditableValue::Object(entries), PathSegment::Key(key)) => {
+                let i = position(entries, key)
+                    .ok_or_else(|| EditError::KeyNotFound(key.to_string()))?;
+                Ok(entries.remove(i).1)
+            }
+            (EditableValue::Array(values), PathSegment::Index(index)) => {
+                if index >= values.len() {
+                    let len = values.len();
+                    return Err(EditError::IndexOutOfBounds { index, len });
+                }
+                Ok(values.remove(index))
+            }
+            (_, segment) => Err(segment_mismatch(segment)),
+        }
+    }
+
+    /// Writes the edited document as JSON.
+    pub fn write_to<W: io::Write + ?Sized>(&self, writer: &mut W, style: Style) -> io::Result<()> {
+        write_io(writer, |out| self.root.write(out, self.s, style, 0))
+    }
+
+    /// Returns the (materialized) container of the last segment of `path` and that segment, or
+    /// `None` for an empty path.
+    fn parent_mut<'p>(
+        &mut self,
+        path: &[PathSegment<'p>],
+    ) -> Result<Option<(&mut EditableValue<'a>, PathSegment<'p>)>, EditError> {
+        let Some((last, parents)) = path.split_last() else {
+            return Ok(None);
+        };
+
+        let mut value = &mut self.root;
+        for segment in parents {
+            value = value.child_mut(self.s, *segment)?;
+        }
+        value.materialize(self.s);
+        Ok(Some((value, *last)))
+    }
+}
+
+/// Writes the document compact, or pretty printed with an indent of 2 spaces with the alternate
+/// flag (`{:#}`).
+impl fmt::Display for EditableAst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = if f.alternate() {
+            Style::Pretty { indent: 2 }
+        } else {
+            Style::Compact
+        };
+        self.root.write(f, self.s, style, 0)
+    }
+}
+
+/// Finds the entry with the given (unescaped) key.
+fn position(entries: &[(Cow<'_, str>, EditableValue<'_>)], key: &str) -> Option<usize> {
+    let key = escape(key);
+    entries.iter().position(|(k, _)| *k == key)
+}
+
+fn segment_mismatch(segment: PathSegment) -> EditError {
+    match segment {
+        PathSegment::Key(key) => EditError::NotAnObject(key.to_string()),
+        PathSegment::Index(index) => EditError::NotAnArray(index),
+    }
+}
+
+/// Escapes a string to be written inside a JSON string.
+fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(|c: char| c == '"' || c == '\\' || c.is_control()) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::custom::parse_json;
+
+    const INPUT: &str = r#"[{"symbol": "A", "firstTradeId": 1, "bidPrice": "1.0", "askPrice": "2.0"},
+        {"symbol": "B", "firstTradeId": 2, "nested": {"x": [1, 2]}}]"#;
+
+    #[test]
+    fn redact_and_enrich() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut doc = EditableAst::new(INPUT, ast.root());
+
+        for i in 0..2 {
+            doc.remove(&[i.into(), "firstTradeId".into()]).unwrap();
+        }
+        doc.insert(&[0.into(), "midPrice".into()], EditableValue::string("1.5"))
+            .unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            r#"[{"symbol":"A","bidPrice":"1.0","askPrice":"2.0","midPrice":"1.5"},{"symbol":"B","nested":{"x":[1,2]}}]"#
+        );
+    }
+
+    #[test]
+    fn unmodified_values_borrow_from_the_input() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut doc = EditableAst::new(INPUT, ast.root());
+        doc.set(&[1.into(), "symbol".into()], EditableValue::string("C"))
+            .unwrap();
+
+        let EditableValue::Array(values) = doc.root() else {
+            panic!("The root should have been materialized");
+        };
+        assert!(matches!(values[0], EditableValue::Original(_)));
+        let EditableValue::Object(entries) = &values[1] else {
+            panic!("The edited object should have been materialized");
+        };
+        assert!(matches!(
+            entries[0],
+            (Cow::Borrowed("symbol"), EditableValue::Scalar(_))
+        ));
+        assert!(matches!(entries[2].1, EditableValue::Original(_)));
+    }
+
+    #[test]
+    fn set_insert_remove() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut doc = EditableAst::new(INPUT, ast.root());
+        let x = |i: usize| [1.into(), "nested".into(), "x".into(), i.into()];
+
+        let previous = doc.set(&x(0), true.into());
+        assert!(matches!(previous, Ok(Some(EditableValue::Original(_)))));
+        doc.insert(&x(2), 3u64.into()).unwrap();
+        doc.insert(&x(0), (-1i64).into()).unwrap();
+        doc.set(&[1.into(), "new \"key\"".into()], 0.5f64.into())
+            .unwrap();
+        doc.set(&[1.into(), "nan".into()], f64::NAN.into()).unwrap();
+        doc.set(&[1.into(), "new \"key\"".into()], 1.5f64.into())
+            .unwrap();
+        doc.remove(&[0.into()]).unwrap();
+
+        assert_eq!(
+            format!("{doc:#}"),
+            r#"[
+  {
+    "symbol": "B",
+    "firstTradeId": 2,
+    "nested": {
+      "x": [
+        -1,
+        true,
+        2,
+        3
+      ]
+    },
+    "new \"key\"": 1.5,
+    "nan": null
+  }
+]"#
+        );
+
+        let mut out = Vec::new();
+        doc.write_to(&mut out, Style::Compact).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), doc.to_string());
+
+        doc.set(&[], EditableValue::null()).unwrap();
+        assert_eq!(doc.to_string(), "null");
+    }
+
+    #[test]
+    fn scalars() {
+        for (value, text) in [
+            (EditableValue::string("a\"b\n"), r#""a\"b\n""#),
+            (
+                EditableValue::number("-96638.25170213e-2").unwrap(),
+                "-96638.25170213e-2",
+            ),
+            (EditableValue::bool(false), "false"),
+            (EditableValue::null(), "null"),
+        ] {
+            let EditableValue::Scalar(scalar) = value else {
+                panic!("{text} should be a scalar");
+            };
+            assert_eq!(scalar.as_str(), text);
+        }
+
+        for text in ["", "-", "01", "1.", "1e", "1 ", "NaN", "1,2", "\"1\""] {
+            assert_eq!(
+                EditableValue::number(text).unwrap_err(),
+                EditError::InvalidNumber(text.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut doc = EditableAst::new(INPUT, ast.root());
+
+        assert_eq!(doc.remove(&[]).unwrap_err(), EditError::EmptyPath);
+        assert_eq!(
+            doc.remove(&[0.into(), "missing".into()]).unwrap_err(),
+            EditError::KeyNotFound("missing".to_string())
+        );
+        assert_eq!(
+            doc.insert(&[0.into(), "symbol".into()], EditableValue::null())
+                .unwrap_err(),
+            EditError::KeyExists("symbol".to_string())
+        );
+        assert_eq!(
+            doc.set(&[5.into()], EditableValue::null()).unwrap_err(),
+            EditError::IndexOutOfBounds { index: 5, len: 2 }
+        );
+        assert_eq!(
+            doc.insert(&[3.into()], EditableValue::null()).unwrap_err(),
+            EditError::IndexOutOfBounds { index: 3, len: 2 }
+        );
+        assert_eq!(
+            doc.remove(&["symbol".into()]).unwrap_err(),
+            EditError::NotAnObject("symbol".to_string())
+        );
+        assert_eq!(
+            doc.remove(&[0.into(), "symbol".into(), 0.into()])
+                .unwrap_err(),
+            EditError::NotAnArray(0)
+        );
+
+        // Failed edits leave the document as it was
+        assert_eq!(doc.to_string(), ast.root().display(INPUT).to_string());
+    }
+}
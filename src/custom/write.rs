@@ -0,0 +1,263 @@
+//! Serialization of the AST back to JSON.
+//!
+//! Scalars are written exactly as they appear in the input: numbers keep their original text and
+//! strings keep their escape sequences, so nothing is lost by parsing and writing a message back.
+//! Only the whitespace between values is replaced, according to the chosen `Style`.
+
+use std::fmt;
+use std::io;
+
+use super::ValueAst;
+
+/// How JSON is laid out by `ValueAst::write_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+    /// Everything in a single line, without any whitespace.
+    #[default]
+    Compact,
+    /// One value per line, with nested values indented by `indent` spaces.
+    Pretty { indent: usize },
+}
+
+impl ValueAst<'_> {
+    /// Writes this value as JSON. `s` must be the input the AST was parsed from.
+    pub fn write_to<W: io::Write + ?Sized>(
+        &self,
+        s: &str,
+        writer: &mut W,
+        style: Style,
+    ) -> io::Result<()> {
+        write_io(writer, |out| write_value(out, s, self, style, 0))
+    }
+
+    /// Returns an object that implements `Display` for this value. `s` must be the input the AST
+    /// was parsed from.
+    ///
+    /// The value is written in the compact style, or pretty printed with an indent of 2 spaces
+    /// with the alternate flag (`{:#}`).
+    pub fn display<'a>(&'a self, s: &'a str) -> DisplayValue<'a> {
+        DisplayValue { value: self, s }
+    }
+}
+
+/// Helper to print a `ValueAst` with `format!` and `{}`, see `ValueAst::display`.
+pub struct DisplayValue<'a> {
+    value: &'a ValueAst<'a>,
+    s: &'a str,
+}
+
+impl fmt::Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = if f.alternate() {
+            Style::Pretty { indent: 2 }
+        } else {
+            Style::Compact
+        };
+        write_value(f, self.s, self.value, style, 0)
+    }
+}
+
+/// Runs `write` on a `fmt::Write` that forwards everything to `writer`.
+pub(super) fn write_io<W: io::Write + ?Sized>(
+    writer: &mut W,
+    write: impl FnOnce(&mut IoAdapter<'_, W>) -> fmt::Result,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: Ok(()),
+    };
+    match write(&mut adapter) {
+        Ok(()) => Ok(()),
+        // `fmt::Error` can only come from the writer, which stored the actual error.
+        Err(fmt::Error) => adapter.error,
+    }
+}
+
+/// Adapts an `io::Write` to `fmt::Write`, keeping the error that `fmt::Error` cannot carry.
+pub(super) struct IoAdapter<'w, W: ?Sized> {
+    inner: &'w mut W,
+    error: io::Result<()>,
+}
+
+impl<W: io::Write + ?Sized> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Err(e);
+            fmt::Error
+        })
+    }
+}
+
+pub(super) fn write_value<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    s: &str,
+    value: &ValueAst,
+    style: Style,
+    depth: usize,
+) -> fmt::Result {
+    match value {
+        ValueAst::Object(object) => write_container(
+            out,
+            style,
+            depth,
+            ('{', '}'),
+            object.iter(),
+            |out, (k, v)| {
+                write_key(out, k.value(s), style)?;
+                write_value(out, s, &v, style, depth + 1)
+            },
+        ),
+        ValueAst::Array(array) => {
+            write_container(out, style, depth, ('[', ']'), array.iter(), |out, v| {
+                write_value(out, s, &v, style, depth + 1)
+            })
+        }
+        scalar => out.write_str(scalar.get_raw_string(s)),
+    }
+}
+
+/// Writes an object or an array at the given depth, using `write_item` to write each of its
+/// items.
+pub(super) fn write_container<W: fmt::Write + ?Sized, T>(
+    out: &mut W,
+    style: Style,
+    depth: usize,
+    (open, close): (char, char),
+    items: impl IntoIterator<Item = T>,
+    mut write_item: impl FnMut(&mut W, T) -> fmt::Result,
+) -> fmt::Result {
+    out.write_char(open)?;
+    let mut empty = true;
+    for item in items {
+        if !empty {
+            out.write_char(',')?;
+        }
+        empty = false;
+        write_newline(out, style, depth + 1)?;
+        write_item(out, item)?;
+    }
+    if !empty {
+        write_newline(out, style, depth)?;
+    }
+    out.write_char(close)
+}
+
+/// Writes the key of an object entry and the separator before its value. `key` is the raw text
+/// of the key, without the quotes.
+pub(super) fn write_key<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    key: &str,
+    style: Style,
+) -> fmt::Result {
+    write!(out, "\"{key}\"")?;
+    out.write_str(match style {
+        Style::Compact => ":",
+        Style::Pretty { .. } => ": ",
+    })
+}
+
+/// In the pretty style, starts a new line indented for the given depth.
+fn write_newline<W: fmt::Write + ?Sized>(out: &mut W, style: Style, depth: usize) -> fmt::Result {
+    match style {
+        Style::Compact => Ok(()),
+        Style::Pretty { indent } => write!(out, "\n{:1$}", "", indent * depth),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::custom::parse_json;
+
+    const INPUT: &str = r#" { "a" : [ 1.50 , -2e+3, true, null ], "b\"c": "é\n",
+        "d": {}, "e": [], "f": { "g": [ {} ] } } "#;
+
+    #[test]
+    fn compact() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut out = Vec::new();
+        ast.root()
+            .write_to(INPUT, &mut out, Style::Compact)
+            .unwrap();
+
+        let expected = r#"{"a":[1.50,-2e+3,true,null],"b\"c":"é\n","d":{},"e":[],"f":{"g":[{}]}}"#;
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(ast.root().display(INPUT).to_string(), expected);
+    }
+
+    #[test]
+    fn pretty() {
+        let ast = parse_json(INPUT).unwrap();
+        let mut out = Vec::new();
+        ast.root()
+            .write_to(INPUT, &mut out, Style::Pretty { indent: 4 })
+            .unwrap();
+
+        let expected = r#"{
+    "a": [
+        1.50,
+        -2e+3,
+        true,
+        null
+    ],
+    "b\"c": "é\n",
+    "d": {},
+    "e": [],
+    "f": {
+        "g": [
+            {}
+        ]
+    }
+}"#;
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(
+            format!("{:#}", ast.root().display(INPUT)),
+            expected.replace("    ", "  ")
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = include_str!("../../single.txt").trim_end();
+        let ast = parse_json(input).unwrap();
+        assert_eq!(ast.root().display(input).to_string(), input);
+
+        let pretty = format!("{:#}", ast.root().display(input));
+        let reparsed = parse_json(&pretty).unwrap();
+        assert_eq!(reparsed.root().display(&pretty).to_string(), input);
+    }
+
+    #[test]
+    fn subset() {
+        let input = r#"[{"symbol": "A", "lastPrice": "1"}, {"symbol": "B", "lastPrice": "2"}]"#;
+        let ast = parse_json(input).unwrap();
+        let ValueAst::Array(array) = ast.root() else {
+            panic!("Value is not an array");
+        };
+        let second = array.iter().nth(1).unwrap();
+        assert_eq!(
+            second.display(input).to_string(),
+            r#"{"symbol":"B","lastPrice":"2"}"#
+        );
+    }
+
+    #[test]
+    fn writer_error() {
+        struct Failing;
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let ast = parse_json("[1]").unwrap();
+        let error = ast
+            .root()
+            .write_to("[1]", &mut Failing, Style::Compact)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "broken pipe");
+    }
+}
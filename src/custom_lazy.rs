@@ -1,7 +1,25 @@
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::marker::PhantomData;
 
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
+use crate::utils::is_json_whitespace;
+
+mod de;
+mod extract;
+mod query;
+mod structural;
+
+pub use binance_derive::LazyExtract;
+pub use de::from_str;
+#[doc(hidden)]
+pub use extract::__private;
+pub use extract::{FromNode, LazyExtract};
+pub use query::{Query, QueryError, QueryIter};
+pub use structural::StructuralIndex;
+
+/// The kind of error found while navigating the document.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
     #[error("The requested element is not an array")]
     NotAnArray,
     #[error("The requested element is not a number")]
@@ -16,234 +34,661 @@ pub enum Error {
     UnterminatedString,
     #[error("The requested index {0} is not present in the array")]
     IndexNotFound(usize),
-    #[error("Unsupported character in number: {0}")]
-    UnsupportedCharInNumber(char),
+    #[error("Invalid number")]
+    InvalidNumber,
+    #[error("The requested number is not an integer")]
+    NotAnInteger,
+    #[error("The number does not fit in the requested type")]
+    NumberOutOfRange,
     #[error("Unexpected value: {0}")]
     UnexpectedValue(char),
     #[error("Unterminated object")]
     UnterminatedObject,
     #[error("Key was not found: {0}")]
     KeyNotFound(std::string::String),
+    #[error("The requested element is not a bool")]
+    NotABool,
+    #[error("The requested element is not null")]
+    NotNull,
+    #[error("Invalid literal, expected `{0}`")]
+    InvalidLiteral(&'static str),
+    #[error("Invalid escape sequence: \\{0}")]
+    InvalidEscape(char),
+    #[error("Invalid unicode escape sequence")]
+    InvalidUnicodeEscape,
+    #[error("Invalid JSON pointer: {0}")]
+    InvalidPointer(std::string::String),
+    #[error("Invalid array index: {0}")]
+    InvalidIndex(std::string::String),
+    #[error("Cannot look up a value inside a string, number, bool or null")]
+    NotAContainer,
+    #[error("Missing field {0}")]
+    MissingField(&'static str),
+    #[error("Unknown field {0}")]
+    UnknownField(std::string::String),
+    #[error("Duplicate field {0}")]
+    DuplicateField(&'static str),
+    #[error("Expected an enum variant, as a string or an object with a single key")]
+    EnumNotSingleKey,
+    #[error("Too many nested objects and arrays")]
+    TooDeep,
+    #[error("{0}")]
+    Custom(std::string::String),
+}
+
+/// Maximum nesting of the objects and arrays inside a skipped value. Skipping without the
+/// structural index is recursive, so deeper values would overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+impl ErrorKind {
+    fn at(self, offset: usize) -> Error {
+        Error { kind: self, offset }
+    }
+}
+
+/// The error type of the lazy parser. Besides the kind of error, it records the byte offset in
+/// the input where the error was found.
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{kind} at byte {offset}")]
+pub struct Error {
+    kind: ErrorKind,
+    offset: usize,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Byte offset in the input where the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the line and column where the error was found. `input` must be the data of the
+    /// document that produced the error.
+    pub fn location(&self, input: &str) -> crate::diagnostics::Location {
+        crate::diagnostics::Location::from_offset(input, self.offset)
+    }
+
+    /// Returns a human readable description of the error, with its location and a snippet of
+    /// the input pointing at it. `input` must be the data of the document that produced the error.
+    pub fn report(&self, input: &str) -> std::string::String {
+        crate::diagnostics::report(&self.kind, input, self.offset)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Clone)]
+/// Decodes a JSON escape sequence. `pos` must point right after the backslash, and is left
+/// pointing right after the escape sequence.
+///
+/// UTF-16 surrogate pairs (`\uD83D\uDE00`) are combined into a single character. On error, only
+/// the kind is returned, the caller knows where the escape sequence started.
+fn decode_escape(data: &str, pos: &mut usize) -> std::result::Result<char, ErrorKind> {
+    fn decode_hex4(bytes: &[u8], pos: &mut usize) -> std::result::Result<u32, ErrorKind> {
+        let digits = bytes
+            .get(*pos..*pos + 4)
+            .ok_or(ErrorKind::InvalidUnicodeEscape)?;
+        let mut value = 0;
+        for &digit in digits {
+            let digit = (digit as char)
+                .to_digit(16)
+                .ok_or(ErrorKind::InvalidUnicodeEscape)?;
+            value = value * 16 + digit;
+        }
+        *pos += 4;
+        Ok(value)
+    }
+
+    let bytes = data.as_bytes();
+    let Some(&escape) = bytes.get(*pos) else {
+        return Err(ErrorKind::UnterminatedString);
+    };
+    *pos += 1;
+
+    let c = match escape {
+        b'"' => '"',
+        b'\\' => '\\',
+        b'/' => '/',
+        b'b' => '\u{8}',
+        b'f' => '\u{c}',
+        b'n' => '\n',
+        b'r' => '\r',
+        b't' => '\t',
+        b'u' => {
+            let high = decode_hex4(bytes, pos)?;
+            let code_point = match high {
+                0xD800..=0xDBFF => {
+                    if !bytes[*pos..].starts_with(b"\\u") {
+                        return Err(ErrorKind::InvalidUnicodeEscape);
+                    }
+                    *pos += 2;
+                    let low = decode_hex4(bytes, pos)?;
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        return Err(ErrorKind::InvalidUnicodeEscape);
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                }
+                0xDC00..=0xDFFF => return Err(ErrorKind::InvalidUnicodeEscape),
+                _ => high,
+            };
+            char::from_u32(code_point).ok_or(ErrorKind::InvalidUnicodeEscape)?
+        }
+        _ => {
+            let c = data[*pos - 1..].chars().next().unwrap_or_default();
+            return Err(ErrorKind::InvalidEscape(c));
+        }
+    };
+    Ok(c)
+}
+
+/// Decodes all the escape sequences in the raw contents of a JSON string. `offset` is the byte
+/// offset of `raw` in the input, used for error reporting.
+fn unescape(raw: &str, offset: usize) -> Result<std::string::String> {
+    let mut decoded = std::string::String::with_capacity(raw.len());
+    let mut pos = 0;
+    while let Some(backslash) = raw[pos..].find('\\').map(|i| pos + i) {
+        decoded.push_str(&raw[pos..backslash]);
+        pos = backslash + 1;
+        let c = decode_escape(raw, &mut pos).map_err(|kind| kind.at(offset + backslash))?;
+        decoded.push(c);
+    }
+    decoded.push_str(&raw[pos..]);
+    Ok(decoded)
+}
+
+/// Compares a raw JSON string (without quotes, possibly containing escape sequences) with `s`,
+/// without allocating. `offset` is the byte offset of `raw` in the input, used for error
+/// reporting.
+pub(crate) fn unescaped_eq(raw: &str, offset: usize, s: &str) -> Result<bool> {
+    let mut pos = 0;
+    let mut s = s;
+    while let Some(backslash) = raw[pos..].find('\\').map(|i| pos + i) {
+        let Some(rest) = s.strip_prefix(&raw[pos..backslash]) else {
+            return Ok(false);
+        };
+        pos = backslash + 1;
+        let c = decode_escape(raw, &mut pos).map_err(|kind| kind.at(offset + backslash))?;
+        let Some(rest) = rest.strip_prefix(c) else {
+            return Ok(false);
+        };
+        s = rest;
+    }
+    Ok(s == &raw[pos..])
+}
+
+/// A position inside the JSON document.
+///
+/// The cursor operates on the raw bytes of the input. Every structural character in JSON is ASCII,
+/// so there is no need to decode UTF-8 while navigating the document. Non-ASCII characters can
+/// only appear inside strings, which are skipped as a whole.
+///
+/// If a `StructuralIndex` is available, skipping over strings, objects and arrays jumps between
+/// the structural characters of the index instead of inspecting every byte.
+#[derive(Clone, Copy)]
 struct Cursor<'a> {
-    iter: std::str::Chars<'a>,
-    current_char: Option<char>,
+    data: &'a str,
+    pos: usize,
+    index: Option<&'a StructuralIndex>,
 }
 
 impl<'a> Cursor<'a> {
-    fn new(data: &'a str) -> Self {
-        let mut iter = data.chars();
+    fn new(data: &'a str, index: Option<&'a StructuralIndex>) -> Self {
         // Initialize the cursor to point to the first non-whitespace character
-        let mut current_char = iter.next();
-        while current_char.is_some_and(|c| c.is_ascii_whitespace()) {
-            current_char = iter.next();
-        }
-        Self { iter, current_char }
+        let mut cursor = Self {
+            data,
+            pos: 0,
+            index,
+        };
+        cursor.skip_whitespace();
+        cursor
     }
 
-    fn advance_character(&mut self) {
-        self.current_char = self.iter.next();
+    /// Byte offset of the current character in the input data.
+    fn offset(&self) -> usize {
+        self.pos
     }
 
-    fn advance_token(&mut self) {
-        self.advance_character();
-        while self.current_char.is_some_and(|c| c.is_ascii_whitespace()) {
-            self.advance_character()
+    /// Returns the byte at the cursor, or `None` at the end of the input.
+    fn peek(&self) -> Option<u8> {
+        self.data.as_bytes().get(self.pos).copied()
+    }
+
+    /// Returns the (possibly non-ASCII) character at the cursor, used for error reporting.
+    fn current_char(&self) -> char {
+        self.data
+            .get(self.pos..)
+            .and_then(|s| s.chars().next())
+            .unwrap_or_default()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_json_whitespace) {
+            self.pos += 1;
         }
     }
 
+    fn advance_token(&mut self) {
+        self.pos += 1;
+        self.skip_whitespace();
+    }
+
     fn advance_value(&mut self) -> Result<()> {
-        let Some(current_char) = self.current_char else {
+        self.advance_nested_value(0)
+    }
+
+    /// Skips a value inside `depth` objects and arrays that are being skipped. The structural
+    /// index is only used for the outermost value: when it is skipped without the index, so are
+    /// its contents.
+    fn advance_nested_value(&mut self, depth: usize) -> Result<()> {
+        let Some(current) = self.peek() else {
             // End of file
             return Ok(());
         };
 
-        match current_char {
-            '{' => {
-                self.advance_object()?;
+        if let Some(index) = self.index.filter(|_| depth == 0) {
+            match current {
+                b'{' | b'[' => return self.advance_container_indexed(index),
+                b'"' => return self.advance_string_indexed(index),
+                _ => {}
             }
-            '"' => {
-                self.advance_string()?;
+        }
+
+        match current {
+            b'{' => {
+                self.advance_object(depth)?;
             }
-            '-' => {
-                self.advance_number()?;
+            b'"' => {
+                self.advance_string()?;
             }
-            c if c.is_ascii_digit() => {
+            b'-' | b'0'..=b'9' => {
                 self.advance_number()?;
             }
-            // These are unimplemented because they are not needed for the API response
-            '[' => {
-                todo!();
+            b'[' => {
+                self.advance_array(depth)?;
             }
-            't' => {
-                todo!();
+            b't' => {
+                self.advance_literal("true")?;
             }
-            'f' => {
-                todo!();
+            b'f' => {
+                self.advance_literal("false")?;
             }
-            'n' => {
-                todo!();
+            b'n' => {
+                self.advance_literal("null")?;
             }
-            _ => return Err(Error::UnexpectedValue(current_char)),
+            _ => return Err(ErrorKind::UnexpectedValue(self.current_char()).at(self.pos)),
         }
 
         Ok(())
     }
 
-    fn advance_object(&mut self) -> Result<()> {
+    fn advance_object(&mut self, depth: usize) -> Result<()> {
+        let start = self.pos;
+        if depth == MAX_DEPTH {
+            return Err(ErrorKind::TooDeep.at(start));
+        }
         self.advance_token();
 
         loop {
-            let Some(current_char) = self.current_char else {
-                return Err(Error::UnterminatedObject);
+            let Some(current) = self.peek() else {
+                return Err(ErrorKind::UnterminatedObject.at(start));
             };
 
-            if current_char == '}' {
+            if current == b'}' {
                 self.advance_token();
                 return Ok(());
             }
 
             self.advance_string()?;
-            if self.current_char.is_some_and(|c| c != ':') {
-                return Err(Error::UnterminatedObject);
+            if self.peek().is_some_and(|b| b != b':') {
+                return Err(ErrorKind::UnterminatedObject.at(self.pos));
             }
             self.advance_token();
 
-            self.advance_value()?;
+            self.advance_nested_value(depth + 1)?;
 
-            if self.current_char.is_some_and(|c| c == ',') {
+            if self.peek() == Some(b',') {
                 self.advance_token();
             }
         }
     }
 
-    fn advance_string(&mut self) -> Result<()> {
-        self.advance_character();
+    fn advance_array(&mut self, depth: usize) -> Result<()> {
+        let start = self.pos;
+        if depth == MAX_DEPTH {
+            return Err(ErrorKind::TooDeep.at(start));
+        }
+        self.advance_token();
 
         loop {
-            let Some(current_char) = self.current_char else {
-                return Err(Error::UnterminatedString);
+            let Some(current) = self.peek() else {
+                return Err(ErrorKind::UnterminatedArray.at(start));
             };
 
-            if current_char == '"' {
+            if current == b']' {
                 self.advance_token();
                 return Ok(());
             }
 
-            if current_char == '\\' {
-                self.advance_character();
-                if self.current_char.is_some_and(|c| c == 'u') {
-                    // skip `\uABCD` patterns
-                    for _ in 0..4 {
-                        self.advance_character();
+            self.advance_nested_value(depth + 1)?;
+
+            if self.peek() == Some(b',') {
+                self.advance_token();
+            }
+        }
+    }
+
+    /// Skips the object or array at the cursor by matching the brackets among the structural
+    /// characters in the index. Unlike `advance_object` and `advance_array`, the contents are
+    /// not validated.
+    ///
+    /// If the brackets do not match, the container is skipped again with `advance_object` or
+    /// `advance_array`, so that the error is the same as without the index.
+    fn advance_container_indexed(&mut self, index: &StructuralIndex) -> Result<()> {
+        let start = self.pos;
+        let bytes = self.data.as_bytes();
+
+        // One bit per open container, set for objects, with the innermost one in the lowest bit
+        let mut open = 0u128;
+        let mut depth = 0;
+        for pos in index.structurals_from(start) {
+            match bytes[pos] {
+                byte @ (b'{' | b'[') => {
+                    if depth == MAX_DEPTH {
+                        break;
+                    }
+                    open = open << 1 | (byte == b'{') as u128;
+                    depth += 1;
+                }
+                byte @ (b'}' | b']') => {
+                    if (open & 1 == 1) != (byte == b'}') {
+                        break;
+                    }
+                    open >>= 1;
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = pos;
+                        self.advance_token();
+                        return Ok(());
                     }
                 }
+                _ => {}
             }
+        }
 
-            self.advance_character();
+        // Mismatched, unterminated, or nested too deeply
+        self.pos = start;
+        if bytes[start] == b'{' {
+            self.advance_object(0)
+        } else {
+            self.advance_array(0)
         }
     }
 
-    fn advance_and_match_string(&mut self, s: &str) -> Result<bool> {
-        self.advance_character();
+    /// Skips the string at the cursor by jumping to its closing quote in the index. Escape
+    /// sequences are not validated.
+    fn advance_string_indexed(&mut self, index: &StructuralIndex) -> Result<()> {
+        let Some(end) = index.structurals_from(self.pos + 1).next() else {
+            let start = self.pos;
+            self.pos = self.data.len();
+            return Err(ErrorKind::UnterminatedString.at(start));
+        };
+        self.pos = end;
+        self.advance_token();
+        Ok(())
+    }
 
-        let mut s_iter = s.chars();
-        let mut matches = true;
+    fn advance_literal(&mut self, literal: &'static str) -> Result<()> {
+        if !self.data.as_bytes()[self.pos..].starts_with(literal.as_bytes()) {
+            return Err(ErrorKind::InvalidLiteral(literal).at(self.pos));
+        }
+        self.pos += literal.len();
+        self.skip_whitespace();
+        Ok(())
+    }
+
+    /// Moves the cursor right after the closing quote of the string at the cursor, without
+    /// skipping any whitespace after it.
+    ///
+    /// Returns the raw contents of the string and whether it contains any escape sequences.
+    fn scan_string(&mut self) -> Result<(&'a str, bool)> {
+        let bytes = self.data.as_bytes();
+        let start = self.pos + 1;
+        let mut pos = start;
+        let mut escaped = false;
 
         loop {
-            let Some(current_char) = self.current_char else {
-                return Err(Error::UnterminatedString);
+            let Some(offset) = bytes[pos..].iter().position(|&b| b == b'"' || b == b'\\') else {
+                self.pos = bytes.len();
+                return Err(ErrorKind::UnterminatedString.at(start - 1));
             };
+            pos += offset;
 
-            if current_char == '"' {
-                matches &= s_iter.next().is_none();
-                self.advance_token();
-                return Ok(matches);
+            if bytes[pos] == b'"' {
+                self.pos = pos + 1;
+                return Ok((&self.data[start..pos], escaped));
             }
 
-            if current_char == '\\' {
-                todo!("string escape support is unimplemented");
-            }
+            let backslash = pos;
+            pos += 1;
+            decode_escape(self.data, &mut pos).map_err(|kind| kind.at(backslash))?;
+            escaped = true;
+        }
+    }
 
-            matches &= s_iter.next().is_some_and(|c| current_char == c);
+    fn advance_string(&mut self) -> Result<()> {
+        self.scan_string()?;
+        self.skip_whitespace();
+        Ok(())
+    }
 
-            self.advance_character();
+    /// Advances over a key, adding it to `keys`, and returns true if it is equal to `s`.
+    fn advance_and_match_key(&mut self, s: &str, keys: &mut KeySet) -> Result<bool> {
+        let raw_offset = self.pos + 1;
+        let (raw, escaped) = self.scan_string()?;
+        self.skip_whitespace();
+        keys.insert(raw, escaped);
+
+        if escaped {
+            unescaped_eq(raw, raw_offset, s)
+        } else {
+            Ok(raw == s)
         }
     }
 
+    /// Returns the raw contents of the string, escape sequences are kept verbatim.
     fn advance_and_get_string(&mut self) -> Result<&'a str> {
-        let start = self.iter.clone();
+        let (raw, _) = self.scan_string()?;
+        self.skip_whitespace();
+        Ok(raw)
+    }
 
-        self.advance_character();
+    /// Like `advance_and_get_string`, but decodes the escape sequences in the string. Only
+    /// allocates if the string actually contains escape sequences.
+    fn advance_and_get_unescaped_string(&mut self) -> Result<Cow<'a, str>> {
+        let raw_offset = self.pos + 1;
+        let (raw, escaped) = self.scan_string()?;
+        self.skip_whitespace();
+
+        if escaped {
+            Ok(Cow::Owned(unescape(raw, raw_offset)?))
+        } else {
+            Ok(Cow::Borrowed(raw))
+        }
+    }
 
-        let mut numbytes = 0;
+    /// Advances over a number, validating it against the RFC 8259 grammar:
+    /// `-? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?`
+    ///
+    /// Returns the raw text of the number.
+    fn advance_number(&mut self) -> Result<&'a str> {
+        let start = self.pos;
 
-        loop {
-            let Some(current_char) = self.current_char else {
-                return Err(Error::UnterminatedString);
-            };
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
 
-            if current_char == '"' {
-                self.advance_token();
-                let substr = &start.as_str()[..numbytes];
-                return Ok(substr);
+        match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                if self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    // Leading zeros are not allowed
+                    return Err(ErrorKind::InvalidNumber.at(self.pos));
+                }
+            }
+            Some(b'1'..=b'9') => {
+                self.advance_digits();
+            }
+            _ => return Err(ErrorKind::InvalidNumber.at(self.pos)),
+        }
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !self.advance_digits() {
+                return Err(ErrorKind::InvalidNumber.at(self.pos));
             }
+        }
 
-            if current_char == '\\' {
-                todo!("string escape support is unimplemented");
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
             }
+            if !self.advance_digits() {
+                return Err(ErrorKind::InvalidNumber.at(self.pos));
+            }
+        }
 
-            numbytes += current_char.len_utf8();
+        let end = self.pos;
+        self.skip_whitespace();
 
-            self.advance_character();
+        Ok(&self.data[start..end])
+    }
+
+    /// Advances over a run of decimal digits. Returns false if there were none.
+    fn advance_digits(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
         }
+        self.pos != start
     }
 
-    fn advance_number(&mut self) -> Result<()> {
-        // we already know the first char is belongs to the number, ignore it
-        self.advance_character();
+    /// Returns a new cursor over the same data, pointing at the given byte offset.
+    fn at(&self, offset: usize) -> Self {
+        Self {
+            pos: offset,
+            ..*self
+        }
+    }
+}
 
-        loop {
-            let Some(current_char) = self.current_char else {
-                return Ok(());
-            };
+/// Position reached by the last lookup on an `Array` or an `Object`, used to resume the following
+/// lookup instead of scanning from the beginning.
+#[derive(Clone, Copy)]
+enum Memo {
+    /// `offset` points at the element with index `index`.
+    Array { index: usize, offset: usize },
+    /// `offset` points at the value of the last key that was found, and `keys` holds the keys of
+    /// that entry and of all the entries before it.
+    Object { offset: usize, keys: KeySet },
+}
 
-            match current_char {
-                c if c.is_ascii_digit() => {}
-                // These characters can be part of the number. For now we will assume we have only
-                // integral numbers, for simplicity.
-                c @ ('.' | 'e' | 'E' | '+' | '-') => return Err(Error::UnsupportedCharInNumber(c)),
-                _ => {
-                    while self.current_char.is_some_and(|c| c.is_ascii_whitespace()) {
-                        self.advance_character();
-                    }
-                    return Ok(());
-                }
-            }
+/// A Bloom filter of the keys of an object, with 2 bits per key. It tells a lookup that resumes
+/// after the last key found whether the entries before it may contain the key too.
+#[derive(Clone, Copy, Default)]
+struct KeySet(u64);
+
+impl KeySet {
+    fn bits(key: &str) -> u64 {
+        // FNV-1a
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        (1 << (hash & 63)) | (1 << ((hash >> 6) & 63))
+    }
 
-            self.advance_character();
-        }
+    /// Adds a raw key. Keys with escape sequences are not decoded, they fill the set instead.
+    fn insert(&mut self, raw: &str, escaped: bool) {
+        self.0 |= if escaped { u64::MAX } else { Self::bits(raw) };
     }
 
-    fn get_char(&self) -> Option<char> {
-        self.current_char
+    fn may_contain(self, key: &str) -> bool {
+        let bits = Self::bits(key);
+        self.0 & bits == bits
     }
 }
 
 pub struct Node<'a, T> {
     cursor: Cursor<'a>,
+    memo: Cell<Option<Memo>>,
     _pd: std::marker::PhantomData<T>,
 }
 
+impl<'a, T> Node<'a, T> {
+    fn from_cursor(cursor: Cursor<'a>) -> Self {
+        Self {
+            cursor,
+            memo: Cell::new(None),
+            _pd: PhantomData,
+        }
+    }
+
+    /// Returns the byte offset of the node in the input data.
+    pub fn offset(&self) -> usize {
+        self.cursor.offset()
+    }
+
+    /// Returns the exact source text of the node. This requires scanning the whole value.
+    pub fn raw_slice(&self) -> Result<&'a str> {
+        let mut end = self.cursor;
+        end.advance_value()?;
+        let raw = &self.cursor.data[self.cursor.offset()..end.offset()];
+        Ok(raw.trim_end_matches(|c| u8::try_from(c).is_ok_and(is_json_whitespace)))
+    }
+
+    /// Resolves a JSON Pointer relative to this node. Each token is looked up with `get_key` or
+    /// `get_index`, so only the part of the input before the referenced value is scanned.
+    fn resolve_pointer(&self, pointer: &str) -> Result<GenericNode<'a>> {
+        let Some(tokens) = crate::pointer::tokens(pointer) else {
+            return Err(ErrorKind::InvalidPointer(pointer.to_string()).at(self.offset()));
+        };
+
+        let mut node = GenericNode::from_cursor(self.cursor);
+        for token in tokens {
+            node = match node.cursor.peek() {
+                Some(b'{') => node.as_object()?.get_key(&token)?,
+                Some(b'[') => {
+                    let Some(index) = crate::pointer::array_index(&token) else {
+                        return Err(ErrorKind::InvalidIndex(token.into_owned()).at(node.offset()));
+                    };
+                    node.as_array()?.get_index(index)?
+                }
+                _ => return Err(ErrorKind::NotAContainer.at(node.offset())),
+            };
+        }
+        Ok(node)
+    }
+}
+
+impl<T> Clone for Node<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            cursor: self.cursor,
+            memo: self.memo.clone(),
+            _pd: PhantomData,
+        }
+    }
+}
+
 pub struct DocumentType {}
 pub struct ArrayType {}
 pub struct ObjectType {}
 pub struct StringType {}
 pub struct NumberType {}
+pub struct BoolType {}
+pub struct NullType {}
 pub struct UnknownType {}
 
 pub type Document<'a> = Node<'a, DocumentType>;
@@ -252,154 +697,530 @@ pub type Array<'a> = Node<'a, ArrayType>;
 pub type Object<'a> = Node<'a, ObjectType>;
 pub type String<'a> = Node<'a, StringType>;
 pub type Number<'a> = Node<'a, NumberType>;
-// Note that Bool and Null types are missing because they are not needed for the purposes of the
-// challenge.
+pub type Bool<'a> = Node<'a, BoolType>;
+pub type Null<'a> = Node<'a, NullType>;
 
 impl<'a> Document<'a> {
     pub fn new(data: &'a str) -> Self {
-        Self {
-            cursor: Cursor::new(data),
-            _pd: std::marker::PhantomData,
-        }
+        Self::from_cursor(Cursor::new(data, None))
     }
 
-    /// Note that there would be more methods to cast the current document to other object types.
-    /// For simplicity, given that they are not needed for this challenge, I did not add them.
+    /// Creates a document that uses the given structural index to navigate the data faster.
+    ///
+    /// Navigating with the index only validates the parts of the document that are actually
+    /// read, while skipped strings, objects and arrays are only checked for their boundaries.
+    /// Mismatched or unterminated brackets are reported with the same error as by `new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index was built for data of a different length.
+    pub fn with_index(data: &'a str, index: &'a StructuralIndex) -> Self {
+        assert_eq!(
+            data.len(),
+            index.len(),
+            "The structural index was built for different data"
+        );
+        Self::from_cursor(Cursor::new(data, Some(index)))
+    }
+
+    /// Casts the document to an array. Together with `as_object`, these are the only casts of a
+    /// document: a top-level string, number, bool or null can be read with `pointer("")`.
     pub fn as_array(self) -> Result<Array<'a>> {
-        if self.cursor.get_char().is_none_or(|c| c != '[') {
-            return Err(Error::NotAnArray);
+        if self.cursor.peek() != Some(b'[') {
+            return Err(ErrorKind::NotAnArray.at(self.cursor.offset()));
         }
-        Ok(Array {
-            cursor: self.cursor.clone(),
-            _pd: PhantomData,
-        })
+        Ok(Array::from_cursor(self.cursor))
+    }
+
+    pub fn as_object(self) -> Result<Object<'a>> {
+        if self.cursor.peek() != Some(b'{') {
+            return Err(ErrorKind::NotAnObject.at(self.cursor.offset()));
+        }
+        Ok(Object::from_cursor(self.cursor))
+    }
+
+    /// Returns the value referenced by a JSON Pointer (RFC 6901), such as `/0/symbol`. The input
+    /// is only scanned up to the referenced value.
+    pub fn pointer(&self, pointer: &str) -> Result<GenericNode<'a>> {
+        self.resolve_pointer(pointer)
     }
 }
 
-// Array should implement an iterator interface that allows us to visit every node.
-// Especially because we cannot tell the length of the array beforehand, it would be
-// useful.
 impl<'a> Array<'a> {
-    pub fn get_index(&self, index: usize) -> Result<GenericNode<'a>> {
-        let mut cursor = self.cursor.clone();
+    /// Returns an iterator over the elements of the array. The iterator moves a single cursor
+    /// forward, so visiting all the elements is linear in the size of the array, unlike calling
+    /// `get_index` for each index.
+    pub fn iter(&self) -> ArrayIter<'a> {
+        let mut cursor = self.cursor;
         cursor.advance_token();
+        ArrayIter {
+            start: self.cursor.offset(),
+            cursor,
+            done: false,
+        }
+    }
+
+    /// Returns the number of elements in the array. Because the length is not known beforehand,
+    /// this needs to scan the whole array.
+    pub fn len(&self) -> Result<usize> {
+        self.iter().try_fold(0, |len, elem| elem.map(|_| len + 1))
+    }
 
-        for _ in 0..index {
-            let Some(current_char) = cursor.current_char else {
-                return Err(Error::UnterminatedArray);
+    /// Returns true if the array has no elements. Only the first element is inspected.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.iter().next().transpose().map(|elem| elem.is_none())
+    }
+
+    /// Returns the element at the given index.
+    ///
+    /// The array remembers the last element it returned, so looking up an index after it resumes
+    /// from there instead of rescanning the array from the beginning. Looking up an earlier index
+    /// scans from the beginning.
+    pub fn get_index(&self, index: usize) -> Result<GenericNode<'a>> {
+        let (mut cursor, skip) = match self.memo.get() {
+            Some(Memo::Array {
+                index: memo_index,
+                offset,
+            }) if memo_index <= index => (self.cursor.at(offset), index - memo_index),
+            _ => {
+                let mut cursor = self.cursor;
+                cursor.advance_token();
+                (cursor, index)
+            }
+        };
+
+        for _ in 0..skip {
+            let Some(current) = cursor.peek() else {
+                return Err(ErrorKind::UnterminatedArray.at(self.cursor.offset()));
             };
 
-            if current_char == ']' {
-                return Err(Error::IndexNotFound(index));
+            if current == b']' {
+                return Err(ErrorKind::IndexNotFound(index).at(cursor.offset()));
             }
 
             cursor.advance_value()?;
 
-            if cursor.current_char.is_some_and(|c| c == ',') {
+            if cursor.peek() == Some(b',') {
                 cursor.advance_token();
             }
         }
 
-        Ok(GenericNode {
-            cursor,
-            _pd: PhantomData,
-        })
+        match cursor.peek() {
+            None => return Err(ErrorKind::UnterminatedArray.at(self.cursor.offset())),
+            Some(b']') => return Err(ErrorKind::IndexNotFound(index).at(cursor.offset())),
+            Some(_) => {}
+        }
+
+        self.memo.set(Some(Memo::Array {
+            index,
+            offset: cursor.offset(),
+        }));
+
+        Ok(GenericNode::from_cursor(cursor))
+    }
+}
+
+/// Iterator over the elements of an `Array`. Created with `Array::iter`.
+pub struct ArrayIter<'a> {
+    /// Offset of the array, used for error reporting.
+    start: usize,
+    cursor: Cursor<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for ArrayIter<'a> {
+    type Item = Result<GenericNode<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let Some(current) = self.cursor.peek() else {
+            self.done = true;
+            return Some(Err(ErrorKind::UnterminatedArray.at(self.start)));
+        };
+
+        if current == b']' {
+            self.done = true;
+            return None;
+        }
+
+        let node = GenericNode::from_cursor(self.cursor);
+
+        if let Err(e) = self.cursor.advance_value() {
+            self.done = true;
+            return Some(Err(e));
+        }
+
+        if self.cursor.peek() == Some(b',') {
+            self.cursor.advance_token();
+        }
+
+        Some(Ok(node))
+    }
+}
+
+impl std::iter::FusedIterator for ArrayIter<'_> {}
+
+impl<'a> IntoIterator for &Array<'a> {
+    type Item = Result<GenericNode<'a>>;
+    type IntoIter = ArrayIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
 impl<'a> GenericNode<'a> {
     pub fn as_array(self) -> Result<Array<'a>> {
-        if self.cursor.get_char().is_none_or(|c| c != '[') {
-            return Err(Error::NotAnArray);
+        if self.cursor.peek() != Some(b'[') {
+            return Err(ErrorKind::NotAnArray.at(self.cursor.offset()));
         }
-        Ok(Array {
-            cursor: self.cursor.clone(),
-            _pd: PhantomData,
-        })
+        Ok(Array::from_cursor(self.cursor))
     }
 
     pub fn as_object(self) -> Result<Object<'a>> {
-        if self.cursor.get_char().is_none_or(|c| c != '{') {
-            return Err(Error::NotAnObject);
+        if self.cursor.peek() != Some(b'{') {
+            return Err(ErrorKind::NotAnObject.at(self.cursor.offset()));
         }
-        Ok(Object {
-            cursor: self.cursor.clone(),
-            _pd: PhantomData,
-        })
+        Ok(Object::from_cursor(self.cursor))
     }
 
     pub fn as_number(self) -> Result<Number<'a>> {
-        if self
-            .cursor
-            .get_char()
-            .is_none_or(|c| c != '-' && !c.is_ascii_digit())
-        {
-            return Err(Error::NotANumber);
+        if !matches!(self.cursor.peek(), Some(b'-' | b'0'..=b'9')) {
+            return Err(ErrorKind::NotANumber.at(self.cursor.offset()));
         }
-        Ok(Number {
-            cursor: self.cursor.clone(),
-            _pd: PhantomData,
-        })
+        Ok(Number::from_cursor(self.cursor))
     }
 
     pub fn as_string(self) -> Result<String<'a>> {
-        if self.cursor.get_char().is_none_or(|c| c != '"') {
-            return Err(Error::NotAString);
+        if self.cursor.peek() != Some(b'"') {
+            return Err(ErrorKind::NotAString.at(self.cursor.offset()));
         }
-        Ok(String {
-            cursor: self.cursor.clone(),
-            _pd: PhantomData,
-        })
+        Ok(String::from_cursor(self.cursor))
+    }
+
+    pub fn as_bool(self) -> Result<Bool<'a>> {
+        if !matches!(self.cursor.peek(), Some(b't' | b'f')) {
+            return Err(ErrorKind::NotABool.at(self.cursor.offset()));
+        }
+        Ok(Bool::from_cursor(self.cursor))
+    }
+
+    pub fn as_null(self) -> Result<Null<'a>> {
+        if !self.is_null() {
+            return Err(ErrorKind::NotNull.at(self.cursor.offset()));
+        }
+        Ok(Null::from_cursor(self.cursor))
+    }
+
+    /// Returns the value referenced by a JSON Pointer (RFC 6901) relative to this node.
+    pub fn pointer(&self, pointer: &str) -> Result<GenericNode<'a>> {
+        self.resolve_pointer(pointer)
+    }
+
+    /// Returns true if the node points to a JSON `null`. Only the first character is inspected,
+    /// the literal itself is validated when the cursor advances over it.
+    pub fn is_null(&self) -> bool {
+        self.cursor.peek() == Some(b'n')
     }
 }
 
 impl<'a> Object<'a> {
+    /// Returns the value of the given key. If the key appears more than once, the first occurrence
+    /// wins.
+    ///
+    /// The object remembers the last key it found and which keys appear up to it, so the next
+    /// lookup resumes right after it and only rescans the earlier entries if the key may be among
+    /// them. Looking up keys in the order in which they appear in the input therefore scans the
+    /// object only once.
     pub fn get_key(&self, s: &str) -> Result<GenericNode<'a>> {
-        let mut cursor = self.cursor.clone();
-        cursor.advance_token();
+        let mut first_entry = self.cursor;
+        first_entry.advance_token();
+
+        let mut keys = KeySet::default();
+        let mut found = None;
+        let mut resume = first_entry;
+        if let Some(Memo::Object {
+            offset,
+            keys: memo_keys,
+        }) = self.memo.get()
+        {
+            if memo_keys.may_contain(s) {
+                found = Self::find_key(first_entry, s, Some(offset), &mut keys)?;
+            }
+            if found.is_none() {
+                keys = memo_keys;
+                resume = self.cursor.at(offset);
+                resume.advance_value()?;
+                if resume.peek() == Some(b',') {
+                    resume.advance_token();
+                }
+            }
+        }
+        if found.is_none() {
+            found = Self::find_key(resume, s, None, &mut keys)?;
+        }
+
+        let Some(node) = found else {
+            return Err(ErrorKind::KeyNotFound(s.to_string()).at(self.cursor.offset()));
+        };
 
+        self.memo.set(Some(Memo::Object {
+            offset: node.cursor.offset(),
+            keys,
+        }));
+        Ok(node)
+    }
+
+    /// Scans the entries of the object starting at `cursor` looking for the key `s`, adding the
+    /// keys it goes over to `keys`. The scan ends at the end of the object, or at the first entry
+    /// that starts at or after `stop`.
+    fn find_key(
+        mut cursor: Cursor<'a>,
+        s: &str,
+        stop: Option<usize>,
+        keys: &mut KeySet,
+    ) -> Result<Option<GenericNode<'a>>> {
         loop {
-            if cursor.current_char.is_some_and(|c| c == '}') {
-                return Err(Error::KeyNotFound(s.to_string()));
+            if cursor.peek() == Some(b'}') || stop.is_some_and(|stop| cursor.offset() >= stop) {
+                return Ok(None);
             }
 
-            let match_found = cursor.advance_and_match_string(s)?;
+            let match_found = cursor.advance_and_match_key(s, keys)?;
 
-            if cursor.current_char.is_none_or(|c| c != ':') {
-                return Err(Error::UnterminatedObject);
+            if cursor.peek() != Some(b':') {
+                return Err(ErrorKind::UnterminatedObject.at(cursor.offset()));
             }
             cursor.advance_token();
 
             if match_found {
-                return Ok(GenericNode {
-                    cursor,
-                    _pd: PhantomData,
-                });
+                return Ok(Some(GenericNode::from_cursor(cursor)));
             } else {
                 cursor.advance_value()?;
             }
 
-            if cursor.current_char.is_some_and(|c| c == ',') {
+            if cursor.peek() == Some(b',') {
                 cursor.advance_token();
             }
         }
     }
+
+    /// Returns an iterator over the `(key, value)` pairs of the object, in the order they appear
+    /// in the input.
+    pub fn iter(&self) -> ObjectIter<'a> {
+        let mut cursor = self.cursor;
+        cursor.advance_token();
+        ObjectIter {
+            start: self.cursor.offset(),
+            cursor,
+            done: false,
+        }
+    }
+
+    /// Looks up several keys in a single pass over the object. Each entry of the result holds the
+    /// value of the key at the same position in `keys`, or `None` if the key is not present.
+    ///
+    /// The scan stops as soon as all the keys have been found. As with `get_key`, the first
+    /// occurrence of a duplicated key wins.
+    pub fn get_keys<const N: usize>(
+        &self,
+        keys: &[&str; N],
+    ) -> Result<[Option<GenericNode<'a>>; N]> {
+        let mut result = [const { None }; N];
+        let mut remaining = N;
+        let mut iter = self.iter();
+
+        while remaining > 0 {
+            let Some(entry) = iter.next_raw() else {
+                break;
+            };
+            let (raw_key, raw_key_offset, value) = entry?;
+
+            for (key, slot) in keys.iter().zip(result.iter_mut()) {
+                if slot.is_none() && unescaped_eq(raw_key, raw_key_offset, key)? {
+                    *slot = Some(value.clone());
+                    remaining -= 1;
+                }
+            }
+        }
+
+        Ok(result)
+    }
 }
 
-impl Number<'_> {
-    pub fn get_value(&self) -> u64 {
-        todo!()
+/// Iterator over the entries of an `Object`. Created with `Object::iter`.
+pub struct ObjectIter<'a> {
+    /// Offset of the object, used for error reporting.
+    start: usize,
+    cursor: Cursor<'a>,
+    done: bool,
+}
+
+impl<'a> ObjectIter<'a> {
+    /// Advances to the next entry, returning the raw (still escaped) key, its offset and the
+    /// value.
+    fn next_raw(&mut self) -> Option<Result<(&'a str, usize, GenericNode<'a>)>> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.advance_entry();
+        match result {
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn advance_entry(&mut self) -> Result<Option<(&'a str, usize, GenericNode<'a>)>> {
+        let cursor = &mut self.cursor;
+        match cursor.peek() {
+            None => return Err(ErrorKind::UnterminatedObject.at(self.start)),
+            Some(b'}') => return Ok(None),
+            Some(b'"') => {}
+            Some(_) => {
+                return Err(ErrorKind::UnexpectedValue(cursor.current_char()).at(cursor.offset()))
+            }
+        }
+
+        let key_offset = cursor.offset() + 1;
+        let key = cursor.advance_and_get_string()?;
+
+        if cursor.peek() != Some(b':') {
+            return Err(ErrorKind::UnterminatedObject.at(cursor.offset()));
+        }
+        cursor.advance_token();
+
+        let value = GenericNode::from_cursor(*cursor);
+        cursor.advance_value()?;
+
+        if cursor.peek() == Some(b',') {
+            cursor.advance_token();
+        }
+
+        Ok(Some((key, key_offset, value)))
+    }
+}
+
+impl<'a> Iterator for ObjectIter<'a> {
+    /// The key is returned as a `String` node, use `String::get_value_unescaped` to decode it.
+    type Item = Result<(String<'a>, GenericNode<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key_cursor = self.cursor;
+        self.next_raw().map(|entry| {
+            entry.map(|(_, _, value)| {
+                let key = String::from_cursor(key_cursor);
+                (key, value)
+            })
+        })
+    }
+}
+
+impl std::iter::FusedIterator for ObjectIter<'_> {}
+
+impl<'a> IntoIterator for &Object<'a> {
+    type Item = Result<(String<'a>, GenericNode<'a>)>;
+    type IntoIter = ObjectIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Number<'a> {
+    /// Returns the raw text of the number, as it appears in the input.
+    pub fn raw(&self) -> Result<&'a str> {
+        let mut cursor = self.cursor;
+        cursor.advance_number()
+    }
+
+    /// Returns the number as an unsigned integer. Fails if the number has a fractional part or
+    /// an exponent, or if it does not fit in a `u64`.
+    pub fn as_u64(&self) -> Result<u64> {
+        let raw = self.integer_raw()?;
+        if let Some(digits) = raw.strip_prefix('-') {
+            // `-0` is valid JSON, any other negative number is out of range.
+            return if digits == "0" {
+                Ok(0)
+            } else {
+                Err(ErrorKind::NumberOutOfRange.at(self.cursor.offset()))
+            };
+        }
+        raw.parse()
+            .map_err(|_| ErrorKind::NumberOutOfRange.at(self.cursor.offset()))
+    }
+
+    /// Returns the number as a signed integer. Fails if the number has a fractional part or an
+    /// exponent, or if it does not fit in an `i64`.
+    pub fn as_i64(&self) -> Result<i64> {
+        self.integer_raw()?
+            .parse()
+            .map_err(|_| ErrorKind::NumberOutOfRange.at(self.cursor.offset()))
+    }
+
+    /// Returns the number as a floating point number. Fails if the number is too large to be
+    /// represented as a finite `f64`.
+    pub fn as_f64(&self) -> Result<f64> {
+        let value: f64 = self
+            .raw()?
+            .parse()
+            .map_err(|_| ErrorKind::InvalidNumber.at(self.cursor.offset()))?;
+        if value.is_infinite() {
+            return Err(ErrorKind::NumberOutOfRange.at(self.cursor.offset()));
+        }
+        Ok(value)
+    }
+
+    fn integer_raw(&self) -> Result<&'a str> {
+        let raw = self.raw()?;
+        if raw.contains(['.', 'e', 'E']) {
+            return Err(ErrorKind::NotAnInteger.at(self.cursor.offset()));
+        }
+        Ok(raw)
+    }
+}
+
+impl Bool<'_> {
+    pub fn get_value(&self) -> Result<bool> {
+        let mut cursor = self.cursor;
+        if cursor.peek() == Some(b't') {
+            cursor.advance_literal("true")?;
+            Ok(true)
+        } else {
+            cursor.advance_literal("false")?;
+            Ok(false)
+        }
     }
 }
 
 impl<'a> String<'a> {
+    /// Returns the raw contents of the string. Escape sequences are not decoded, use
+    /// `get_value_unescaped` if the string may contain them.
     pub fn get_value(&self) -> Result<&'a str> {
-        let mut cursor = self.cursor.clone();
+        let mut cursor = self.cursor;
         cursor.advance_and_get_string()
     }
 
+    /// Returns the contents of the string with all escape sequences decoded. The result borrows
+    /// from the input unless the string contains escape sequences.
+    pub fn get_value_unescaped(&self) -> Result<Cow<'a, str>> {
+        let mut cursor = self.cursor;
+        cursor.advance_and_get_unescaped_string()
+    }
+
     pub fn get_value_as_f64(&self) -> Result<f64> {
-        self.get_value()?.parse().map_err(|_| Error::NotANumber)
+        self.get_value()?
+            .parse()
+            .map_err(|_| ErrorKind::NotANumber.at(self.cursor.offset()))
     }
 }
 
@@ -409,31 +1230,662 @@ pub mod test {
 
     #[test]
     fn cursor_number() {
-        let mut cursor = Cursor::new("1234543, ");
+        let mut cursor = Cursor::new("1234543, ", None);
         cursor.advance_value().unwrap();
 
-        assert!(cursor.current_char.is_some_and(|c| c == ','));
+        assert!(cursor.peek() == Some(b','));
 
-        let mut cursor = Cursor::new("-1234543, ");
+        let mut cursor = Cursor::new("-1234543, ", None);
         cursor.advance_value().unwrap();
 
-        assert!(cursor.current_char.is_some_and(|c| c == ','));
+        assert!(cursor.peek() == Some(b','));
+    }
+
+    #[test]
+    fn cursor_number_grammar() {
+        for valid in ["0", "-0", "12", "-1.5", "0.25", "1e5", "1E+5", "-2.5e-3"] {
+            let input = format!("{valid} ,");
+            let mut cursor = Cursor::new(&input, None);
+            assert_eq!(cursor.advance_number().unwrap(), valid);
+            assert!(cursor.peek() == Some(b','));
+        }
+
+        for invalid in ["-", "01", "1.", "1.e5", "1e", "1e+", "-.5"] {
+            let mut cursor = Cursor::new(invalid, None);
+            assert!(
+                matches!(
+                    cursor.advance_value(),
+                    Err(Error {
+                        kind: ErrorKind::InvalidNumber,
+                        ..
+                    })
+                ),
+                "{invalid} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn number_accessors() {
+        let doc = Document::new(
+            r###"[1732905664238, -45, 2.5e2, 18446744073709551616, 1e400, -0, 9223372036854775808]"###,
+        );
+        let array = doc.as_array().unwrap();
+        let number = |i| array.get_index(i).unwrap().as_number().unwrap();
+
+        assert_eq!(number(0).as_u64().unwrap(), 1732905664238);
+        assert_eq!(number(0).as_i64().unwrap(), 1732905664238);
+        assert_eq!(number(0).raw().unwrap(), "1732905664238");
+
+        assert!(matches!(
+            number(1).as_u64(),
+            Err(Error {
+                kind: ErrorKind::NumberOutOfRange,
+                ..
+            })
+        ));
+        assert_eq!(number(1).as_i64().unwrap(), -45);
+        assert_eq!(number(1).as_f64().unwrap(), -45.0);
+
+        assert!(matches!(
+            number(2).as_u64(),
+            Err(Error {
+                kind: ErrorKind::NotAnInteger,
+                ..
+            })
+        ));
+        assert_eq!(number(2).as_f64().unwrap(), 250.0);
+
+        assert!(matches!(
+            number(3).as_u64(),
+            Err(Error {
+                kind: ErrorKind::NumberOutOfRange,
+                ..
+            })
+        ));
+        assert!(matches!(
+            number(4).as_f64(),
+            Err(Error {
+                kind: ErrorKind::NumberOutOfRange,
+                ..
+            })
+        ));
+        assert_eq!(number(5).as_u64().unwrap(), 0);
+        assert!(matches!(
+            number(6).as_i64(),
+            Err(Error {
+                kind: ErrorKind::NumberOutOfRange,
+                ..
+            })
+        ));
+        assert_eq!(number(6).as_u64().unwrap(), 9223372036854775808);
     }
 
     #[test]
     fn cursor_string() {
-        let mut cursor = Cursor::new(r##""This is a string with escaped \" characters","##);
+        let mut cursor = Cursor::new(r##""This is a string with escaped \" characters","##, None);
         cursor.advance_value().unwrap();
 
-        assert!(cursor.current_char.is_some_and(|c| c == ','));
+        assert!(cursor.peek() == Some(b','));
+    }
+
+    #[test]
+    fn cursor_invalid_escape() {
+        let mut cursor = Cursor::new(r##""\x""##, None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::InvalidEscape('x'),
+                ..
+            })
+        ));
+
+        let mut cursor = Cursor::new(r##""\u12G4""##, None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::InvalidUnicodeEscape,
+                ..
+            })
+        ));
+
+        // Lone low surrogate
+        let mut cursor = Cursor::new(r##""\uDE00""##, None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::InvalidUnicodeEscape,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn escaped_strings() {
+        let doc = Document::new(
+            r###"[{"sym\"bol": "BTC\/USD", "plain": "ETH", "emoji": "\ud83d\ude00 \u00e9\n", "a\u0062c": 1}]"###,
+        );
+        let object = doc
+            .as_array()
+            .unwrap()
+            .get_index(0)
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let symbol = object.get_key("sym\"bol").unwrap().as_string().unwrap();
+        assert_eq!(symbol.get_value().unwrap(), r"BTC\/USD");
+        let unescaped = symbol.get_value_unescaped().unwrap();
+        assert!(matches!(unescaped, Cow::Owned(_)));
+        assert_eq!(unescaped, "BTC/USD");
+
+        let plain = object.get_key("plain").unwrap().as_string().unwrap();
+        let unescaped = plain.get_value_unescaped().unwrap();
+        assert!(matches!(unescaped, Cow::Borrowed("ETH")));
+
+        let emoji = object.get_key("emoji").unwrap().as_string().unwrap();
+        assert_eq!(emoji.get_value_unescaped().unwrap(), "\u{1F600} \u{e9}\n");
+
+        assert!(object.get_key("abc").unwrap().as_number().is_ok());
+        assert!(matches!(
+            object.get_key("a\\u0062c"),
+            Err(Error {
+                kind: ErrorKind::KeyNotFound(_),
+                ..
+            })
+        ));
     }
 
     #[test]
     fn cursor_object() {
-        let mut cursor = Cursor::new(r##"{"key":-124,"key2":"","key3":1544}."##);
+        let mut cursor = Cursor::new(r##"{"key":-124,"key2":"","key3":1544}."##, None);
         cursor.advance_value().unwrap();
 
-        assert!(cursor.current_char.is_some_and(|c| c == '.'));
+        assert!(cursor.peek() == Some(b'.'));
+    }
+
+    #[test]
+    fn cursor_array() {
+        let mut cursor = Cursor::new(r##"[1, "two", [3, [ ]], {"four": [4]}, true] ."##, None);
+        cursor.advance_value().unwrap();
+
+        assert!(cursor.peek() == Some(b'.'));
+
+        let mut cursor = Cursor::new("[1, 2", None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::UnterminatedArray,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn cursor_literals() {
+        for literal in ["true", "false", "null"] {
+            let input = format!("{literal} ,");
+            let mut cursor = Cursor::new(&input, None);
+            cursor.advance_value().unwrap();
+
+            assert!(cursor.peek() == Some(b','));
+        }
+
+        let mut cursor = Cursor::new("nul", None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::InvalidLiteral("null"),
+                ..
+            })
+        ));
+
+        let mut cursor = Cursor::new("trve", None);
+        assert!(matches!(
+            cursor.advance_value(),
+            Err(Error {
+                kind: ErrorKind::InvalidLiteral("true"),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn json_whitespace() {
+        let doc = Document::new(" \t\r\n[1,\n\t2 ] ");
+        assert_eq!(doc.pointer("").unwrap().raw_slice().unwrap(), "[1,\n\t2 ]");
+        let array = doc.as_array().unwrap();
+        assert_eq!(array.get_index(1).unwrap().raw_slice().unwrap(), "2");
+
+        // Form feed is whitespace for `u8::is_ascii_whitespace`, but not for JSON
+        let doc = Document::new("[1,\x0c2]");
+        let array = doc.as_array().unwrap();
+        let error = array
+            .get_index(1)
+            .and_then(|node| node.raw_slice())
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedValue('\x0c'));
+        assert_eq!(error.offset(), 3);
+        assert!(Document::new("\x0c[]").as_array().is_err());
+    }
+
+    #[test]
+    fn document_bool_and_null() {
+        let doc = Document::new(r###"[ {"a": [1, null], "b": true, "c": null, "d": false} ]"###);
+
+        let object = doc
+            .as_array()
+            .unwrap()
+            .get_index(0)
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let b = object.get_key("b").unwrap().as_bool().unwrap();
+        assert!(b.get_value().unwrap());
+
+        let d = object.get_key("d").unwrap().as_bool().unwrap();
+        assert!(!d.get_value().unwrap());
+
+        let c = object.get_key("c").unwrap();
+        assert!(c.is_null());
+        assert!(c.as_null().is_ok());
+
+        assert!(!object.get_key("b").unwrap().is_null());
+        assert!(matches!(
+            object.get_key("a").unwrap().as_bool(),
+            Err(Error {
+                kind: ErrorKind::NotABool,
+                ..
+            })
+        ));
+        assert!(matches!(
+            object.get_key("d").unwrap().as_null(),
+            Err(Error {
+                kind: ErrorKind::NotNull,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn array_iter() {
+        let doc = Document::new(r###"[ {"a": [1, 2]}, "b", 3, [], true ]"###);
+        let array = doc.as_array().unwrap();
+
+        let elems: Vec<_> = array.iter().collect::<Result<_>>().unwrap();
+        assert_eq!(elems.len(), 5);
+        assert!(elems[0].cursor.peek() == Some(b'{'));
+        assert!(elems[1].cursor.peek() == Some(b'"'));
+        assert!(elems[2].cursor.peek() == Some(b'3'));
+        assert!(elems[3].cursor.peek() == Some(b'['));
+        assert!(elems[4].cursor.peek() == Some(b't'));
+
+        assert_eq!(array.len().unwrap(), 5);
+        assert!(!array.is_empty().unwrap());
+
+        let mut count = 0;
+        for elem in &array {
+            elem.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 5);
+
+        let empty = Document::new("[ ]").as_array().unwrap();
+        assert_eq!(empty.len().unwrap(), 0);
+        assert!(empty.is_empty().unwrap());
+    }
+
+    #[test]
+    fn array_iter_unterminated() {
+        let array = Document::new("[1, 2").as_array().unwrap();
+        let mut iter = array.iter();
+
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(Error {
+                kind: ErrorKind::UnterminatedArray,
+                ..
+            }))
+        ));
+        assert!(iter.next().is_none());
+        assert!(matches!(
+            array.len(),
+            Err(Error {
+                kind: ErrorKind::UnterminatedArray,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn object_iter() {
+        let doc = Document::new(r###"{"a": [1, {"x": 2}], "b\u0021" : "c", "d": null}"###);
+        let object = doc.as_object().unwrap();
+
+        let entries: Vec<_> = object.iter().collect::<Result<_>>().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let keys: Vec<_> = entries
+            .iter()
+            .map(|(k, _)| k.get_value_unescaped().unwrap())
+            .collect();
+        assert_eq!(keys, ["a", "b!", "d"]);
+
+        assert!(entries[0].1.cursor.peek() == Some(b'['));
+        assert!(entries[1].1.cursor.peek() == Some(b'"'));
+        assert!(entries[2].1.is_null());
+
+        let empty = Document::new("{ }").as_object().unwrap();
+        assert!(empty.iter().next().is_none());
+
+        let unterminated = Document::new(r#"{"a": 1"#).as_object().unwrap();
+        let mut iter = unterminated.iter();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(Error {
+                kind: ErrorKind::UnterminatedObject,
+                ..
+            }))
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn object_get_keys() {
+        let doc = Document::new(
+            r###"{"symbol":"BTC-241206-115000-C","priceChange":"-45","bidPrice":"50","askPrice":"60","sym\u0062ol":"dup"}"###,
+        );
+        let object = doc.as_object().unwrap();
+
+        let [symbol, bid_price, ask_price, missing] = object
+            .get_keys(&["symbol", "bidPrice", "askPrice", "missing"])
+            .unwrap();
+
+        let value = |node: Option<GenericNode>| {
+            node.unwrap()
+                .as_string()
+                .unwrap()
+                .get_value()
+                .unwrap()
+                .to_owned()
+        };
+        assert_eq!(value(symbol), "BTC-241206-115000-C");
+        assert_eq!(value(bid_price), "50");
+        assert_eq!(value(ask_price), "60");
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn array_memoization() {
+        let doc = Document::new(r###"[10, 11, 12, 13]"###);
+        let array = doc.as_array().unwrap();
+        let value = |i| {
+            array
+                .get_index(i)
+                .unwrap()
+                .as_number()
+                .unwrap()
+                .as_u64()
+                .unwrap()
+        };
+
+        let memo_index = || match array.memo.get() {
+            Some(Memo::Array { index, .. }) => index,
+            _ => panic!("Expected an array memo"),
+        };
+        assert_eq!(value(1), 11);
+        assert_eq!(memo_index(), 1);
+        assert_eq!(value(3), 13);
+        assert_eq!(memo_index(), 3);
+        // Going backwards rescans from the beginning
+        assert_eq!(value(0), 10);
+        assert_eq!(value(2), 12);
+        assert_eq!(value(2), 12);
+
+        assert!(matches!(
+            array.get_index(4),
+            Err(Error {
+                kind: ErrorKind::IndexNotFound(4),
+                ..
+            })
+        ));
+        assert!(matches!(
+            array.get_index(9),
+            Err(Error {
+                kind: ErrorKind::IndexNotFound(9),
+                ..
+            })
+        ));
+        assert_eq!(value(3), 13);
+    }
+
+    #[test]
+    fn object_memoization() {
+        let doc = Document::new(r###"{"a": 1, "b": [2, 3], "c": {"d": 4}, "e": 5}"###);
+        let object = doc.as_object().unwrap();
+        let key = |k| object.get_key(k).unwrap().cursor.current_char();
+
+        assert_eq!(key("a"), '1');
+        assert_eq!(key("c"), '{');
+        assert_eq!(key("e"), '5');
+        // Goes back to the start of the object
+        assert_eq!(key("b"), '[');
+        assert_eq!(key("b"), '[');
+        assert_eq!(key("a"), '1');
+        assert!(matches!(
+            object.get_key("f"),
+            Err(Error {
+                kind: ErrorKind::KeyNotFound(_),
+                ..
+            })
+        ));
+        assert_eq!(key("e"), '5');
+    }
+
+    #[test]
+    fn object_memoization_duplicate_keys() {
+        let doc = Document::new(r###"{"a": 1, "b": 2, "a": 3, "c": 4}"###);
+        let key = |object: &Object, k| object.get_key(k).unwrap().cursor.current_char();
+
+        let object = doc.clone().as_object().unwrap();
+        assert_eq!(key(&object, "a"), '1');
+
+        // A lookup in between must not change the answer
+        for between in ["b", "c"] {
+            let object = doc.clone().as_object().unwrap();
+            assert_eq!(
+                key(&object, between),
+                if between == "b" { '2' } else { '4' }
+            );
+            assert_eq!(key(&object, "a"), '1');
+            assert_eq!(key(&object, "a"), '1');
+        }
+
+        // Keys with escape sequences are matched after decoding
+        let doc = Document::new(r###"{"b": 0, "\u0061": 1, "c": 2, "a": 3}"###);
+        let object = doc.as_object().unwrap();
+        assert_eq!(key(&object, "c"), '2');
+        assert_eq!(key(&object, "a"), '1');
+    }
+
+    #[test]
+    fn node_offset_and_raw_slice() {
+        let input = r###"[ {"a": [1, 2 ] , "é": "ü" }, -1.5e3 , null ]"###;
+        let array = Document::new(input).as_array().unwrap();
+        assert_eq!(array.offset(), 0);
+        assert_eq!(array.raw_slice().unwrap(), input);
+
+        let object = array.get_index(0).unwrap();
+        assert_eq!(object.offset(), 2);
+        assert_eq!(
+            object.raw_slice().unwrap(),
+            r###"{"a": [1, 2 ] , "é": "ü" }"###
+        );
+
+        let object = object.as_object().unwrap();
+        let inner = object.get_key("a").unwrap();
+        assert_eq!(inner.offset(), 8);
+        assert_eq!(inner.raw_slice().unwrap(), "[1, 2 ]");
+
+        let string = object.get_key("é").unwrap();
+        assert_eq!(&input[string.offset()..], r###""ü" }, -1.5e3 , null ]"###);
+        assert_eq!(string.raw_slice().unwrap(), r###""ü""###);
+
+        assert_eq!(array.get_index(1).unwrap().raw_slice().unwrap(), "-1.5e3");
+        assert_eq!(array.get_index(2).unwrap().raw_slice().unwrap(), "null");
+    }
+
+    /// Walks the whole document with and without the structural index, checking that both give
+    /// the same results.
+    fn assert_indexed_navigation_matches(input: &str) {
+        fn walk(node: GenericNode, out: &mut Vec<(usize, std::string::String)>) {
+            out.push((node.offset(), node.raw_slice().unwrap().to_owned()));
+            if let Ok(array) = node.clone().as_array() {
+                for elem in &array {
+                    walk(elem.unwrap(), out);
+                }
+            } else if let Ok(object) = node.as_object() {
+                for entry in &object {
+                    let (key, value) = entry.unwrap();
+                    out.push((key.offset(), key.get_value().unwrap().to_owned()));
+                    walk(value, out);
+                }
+            }
+        }
+
+        let index = StructuralIndex::new(input);
+        let scalar = Document::new(input).as_array().unwrap();
+        let indexed = Document::with_index(input, &index).as_array().unwrap();
+
+        let mut expected = vec![];
+        let mut actual = vec![];
+        walk(scalar.get_index(0).unwrap(), &mut expected);
+        walk(indexed.get_index(0).unwrap(), &mut actual);
+        assert_eq!(actual, expected);
+
+        let len = scalar.len().unwrap();
+        assert_eq!(indexed.len().unwrap(), len);
+        for i in [len - 1, len / 2, 0] {
+            let scalar = scalar.get_index(i).unwrap();
+            let indexed = indexed.get_index(i).unwrap();
+            assert_eq!(indexed.offset(), scalar.offset());
+            assert_eq!(indexed.raw_slice().unwrap(), scalar.raw_slice().unwrap());
+        }
+    }
+
+    #[test]
+    fn indexed_navigation() {
+        assert_indexed_navigation_matches(
+            r###"[{"a\"]": [1, "x,y]", {"b": "}]\\"}], "c": null}, [[], {}], "\"", -2.5e3, true]"###,
+        );
+        assert_indexed_navigation_matches(include_str!("../single.txt"));
+        assert_indexed_navigation_matches(include_str!("../request.txt"));
+    }
+
+    #[test]
+    fn indexed_errors() {
+        for (input, expected) in [
+            (r#"[{"a": [1, 2}"#, "UnexpectedValue('}')"),
+            (r#"[{"a": [1, 2}, 5]"#, "UnexpectedValue('}')"),
+            (r#"[{"a": [1, 2]"#, "UnterminatedObject"),
+            (r#"[[1, "]"#, "UnterminatedString"),
+            (r#"[[1, []"#, "UnterminatedArray"),
+            (r#"[{"a": {]}, 5]"#, "UnterminatedString"),
+            (r#"[[{]], 5]"#, "UnterminatedString"),
+            (r#"["abc"#, "UnterminatedString"),
+        ] {
+            let index = StructuralIndex::new(input);
+            let indexed = Document::with_index(input, &index).as_array().unwrap();
+            let Err(error) = indexed.get_index(1) else {
+                panic!("{input} should fail");
+            };
+            assert_eq!(format!("{:?}", error.kind()), expected, "{input}");
+
+            // Same error as without the index
+            let scalar = Document::new(input).as_array().unwrap();
+            let Err(scalar) = scalar.get_index(1) else {
+                panic!("{input} should fail without the index");
+            };
+            assert_eq!(
+                (error.kind(), error.offset()),
+                (scalar.kind(), scalar.offset()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth| format!("[{}{}, 1]", "[".repeat(depth), "]".repeat(depth));
+        let deep = format!("[{}", r#"{"a": ["#.repeat(100000));
+
+        let input = nested(MAX_DEPTH);
+        let index = StructuralIndex::new(&input);
+        for document in [Document::new(&input), Document::with_index(&input, &index)] {
+            let array = document.as_array().unwrap();
+            assert_eq!(array.get_index(1).unwrap().cursor.current_char(), '1');
+        }
+
+        for (input, offset) in [
+            (&nested(MAX_DEPTH + 1), 1 + MAX_DEPTH),
+            (&nested(200000), 1 + MAX_DEPTH),
+            (&deep, 1 + 7 * MAX_DEPTH / 2),
+        ] {
+            let index = StructuralIndex::new(input);
+            for document in [Document::new(input), Document::with_index(input, &index)] {
+                let array = document.as_array().unwrap();
+                let Err(error) = array.get_index(1) else {
+                    panic!("Nesting deeper than {MAX_DEPTH} should fail");
+                };
+                assert_eq!(
+                    (error.kind(), error.offset()),
+                    (&ErrorKind::TooDeep, offset)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        let input = "[\n  {\"a\": 1},\n  {\"b\": tru}\n]";
+        let array = Document::new(input).as_array().unwrap();
+
+        let error = array
+            .get_index(1)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .get_key("b")
+            .unwrap()
+            .as_bool()
+            .unwrap()
+            .get_value()
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidLiteral("true"));
+        assert_eq!(error.offset(), input.find("tru").unwrap());
+        assert_eq!(
+            error.location(input),
+            crate::diagnostics::Location { line: 3, column: 9 }
+        );
+        assert_eq!(
+            error.report(input),
+            "Invalid literal, expected `true` at line 3, column 9\n  {\"b\": tru}\n        ^"
+        );
+
+        let array = Document::new("[1, 2]").as_array().unwrap();
+        let Err(error) = array.get_index(3) else {
+            panic!("index 3 should not exist");
+        };
+        assert_eq!(error.offset(), 5);
+
+        let input = r#"{"a": "x\q"}"#;
+        let value = Document::new(input).as_object().unwrap().get_key("a");
+        let error = value.unwrap().as_string().unwrap().get_value_unescaped();
+        assert_eq!(error.unwrap_err().offset(), input.find('\\').unwrap());
     }
 
     #[test]
@@ -443,17 +1895,17 @@ pub mod test {
         let array = doc.as_array().unwrap();
 
         let index0 = array.get_index(0).unwrap();
-        assert!(index0.cursor.current_char.is_some_and(|c| c == '{'));
+        assert!(index0.cursor.peek() == Some(b'{'));
 
         let index1 = array.get_index(1).unwrap();
-        assert!(index1.cursor.current_char.is_some_and(|c| c == '3'));
+        assert!(index1.cursor.peek() == Some(b'3'));
 
         let object = index0.as_object().unwrap();
         let _number = index1.as_number().unwrap();
 
         let inner_value = object.get_key("a").unwrap();
 
-        assert!(inner_value.cursor.current_char.is_some_and(|c| c == '5'));
+        assert!(inner_value.cursor.peek() == Some(b'5'));
         let _number = inner_value.as_number();
 
         let inner_value = object.get_key("b").unwrap();
@@ -461,4 +1913,48 @@ pub mod test {
         let c = inner_value.as_string().unwrap().get_value().unwrap();
         assert_eq!(c, "c");
     }
+
+    #[test]
+    fn pointer() {
+        let input = r#"[{"symbol": "A", "a/b": [1, {"m~n": null}], "": 2}, 3]"#;
+        let doc = Document::new(input);
+        let raw = |pointer| doc.pointer(pointer).and_then(|node| node.raw_slice());
+
+        assert_eq!(raw("").unwrap(), input);
+        assert_eq!(raw("/0/symbol").unwrap(), r#""A""#);
+        assert_eq!(raw("/0/a~1b/1/m~0n").unwrap(), "null");
+        assert_eq!(raw("/0/").unwrap(), "2");
+        assert_eq!(raw("/1").unwrap(), "3");
+
+        let entry = doc.pointer("/0/a~1b").unwrap();
+        assert_eq!(
+            entry.pointer("/1/m~0n").unwrap().raw_slice().unwrap(),
+            "null"
+        );
+
+        let kind = |pointer| raw(pointer).unwrap_err().kind().clone();
+        assert_eq!(kind("0"), ErrorKind::InvalidPointer("0".to_string()));
+        assert_eq!(kind("/~2"), ErrorKind::InvalidPointer("/~2".to_string()));
+        assert_eq!(kind("/2"), ErrorKind::IndexNotFound(2));
+        assert_eq!(kind("/01"), ErrorKind::InvalidIndex("01".to_string()));
+        assert_eq!(
+            kind("/0/missing"),
+            ErrorKind::KeyNotFound("missing".to_string())
+        );
+        assert_eq!(kind("/1/0"), ErrorKind::NotAContainer);
+        assert_eq!(
+            raw("/0/symbol/x").unwrap_err().offset(),
+            input.find("\"A").unwrap()
+        );
+    }
+
+    #[test]
+    fn pointer_is_lazy() {
+        // Everything after the referenced value is never scanned
+        let input = r#"[{"symbol": "A", "b": tru"#;
+        let doc = Document::new(input);
+        let symbol = doc.pointer("/0/symbol").unwrap().as_string().unwrap();
+        assert_eq!(symbol.get_value().unwrap(), "A");
+        assert!(doc.pointer("/1").is_err());
+    }
 }
@@ -0,0 +1,652 @@
+//! A `serde::Deserializer` over the nodes of a lazy `Document`.
+//!
+//! Any type implementing `serde::Deserialize` can be read from a `GenericNode` with
+//! `T::deserialize(node)`, or from a whole message with `from_str`. Strings without escape
+//! sequences are borrowed from the input, so `&'a str` fields work as with `serde_json`.
+//!
+//! The deserializer moves a cursor through the value, like the iterators of `Array` and `Object`
+//! do, so every byte is only read once. Values that the target type ignores (e.g. unknown fields
+//! of a struct) are skipped over without being decoded.
+
+use std::borrow::Cow;
+
+use serde::de::{self, Visitor};
+
+use super::{unescape, Cursor, Document, Error, ErrorKind, GenericNode, Result};
+
+/// Offset of the errors created by `serde::de::Error::custom`, which don't know where they
+/// happened. It is replaced with the offset of the value that was being deserialized.
+const UNKNOWN_OFFSET: usize = usize::MAX;
+
+/// Deserializes a value of type `T` from the whole message.
+///
+/// As with the rest of the lazy API, the data after the first value is not validated.
+pub fn from_str<'a, T: de::Deserialize<'a>>(data: &'a str) -> Result<T> {
+    T::deserialize(&mut Deserializer {
+        cursor: Document::new(data).cursor,
+    })
+}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ErrorKind::Custom(msg.to_string()).at(UNKNOWN_OFFSET)
+    }
+}
+
+impl Error {
+    fn or_offset(mut self, offset: usize) -> Self {
+        if self.offset == UNKNOWN_OFFSET {
+            self.offset = offset;
+        }
+        self
+    }
+}
+
+struct Deserializer<'a> {
+    cursor: Cursor<'a>,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Reads the string at the cursor, borrowing it from the input unless it contains escape
+    /// sequences.
+    fn visit_string<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        let raw_offset = self.cursor.offset() + 1;
+        let (raw, escaped) = self.cursor.scan_string()?;
+        self.cursor.skip_whitespace();
+        if escaped {
+            visitor.visit_string(unescape(raw, raw_offset)?)
+        } else {
+            visitor.visit_borrowed_str(raw)
+        }
+    }
+
+    /// Reads the key of an object entry and the colon after it.
+    fn key<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<K::Value> {
+        if self.cursor.peek() != Some(b'"') {
+            let c = self.cursor.current_char();
+            return Err(ErrorKind::UnexpectedValue(c).at(self.cursor.offset()));
+        }
+        let offset = self.cursor.offset();
+        let key = seed
+            .deserialize(KeyDeserializer { de: self })
+            .map_err(|e| e.or_offset(offset))?;
+
+        if self.cursor.peek() != Some(b':') {
+            return Err(ErrorKind::UnterminatedObject.at(self.cursor.offset()));
+        }
+        self.cursor.advance_token();
+        Ok(key)
+    }
+
+    /// Visits an object or an array with `visit`, and then skips the entries that the visitor
+    /// did not read.
+    fn visit_container<T>(
+        &mut self,
+        visit: impl FnOnce(&mut Self, &mut bool) -> Result<T>,
+    ) -> Result<T> {
+        let start = self.cursor;
+        self.cursor.advance_token();
+        let mut done = false;
+        let value = visit(self, &mut done)?;
+        if !done {
+            self.cursor = start;
+            self.cursor.advance_value()?;
+        }
+        Ok(value)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let offset = self.cursor.offset();
+        let result = match self.cursor.peek() {
+            Some(b'{') => self.visit_container(|de, done| {
+                visitor.visit_map(MapAccess {
+                    de,
+                    start: offset,
+                    done,
+                })
+            }),
+            Some(b'[') => self.visit_container(|de, done| {
+                visitor.visit_seq(SeqAccess {
+                    de,
+                    start: offset,
+                    done,
+                })
+            }),
+            Some(b'"') => self.visit_string(visitor),
+            Some(b't') => {
+                self.cursor.advance_literal("true")?;
+                visitor.visit_bool(true)
+            }
+            Some(b'f') => {
+                self.cursor.advance_literal("false")?;
+                visitor.visit_bool(false)
+            }
+            Some(b'n') => {
+                self.cursor.advance_literal("null")?;
+                visitor.visit_unit()
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let raw = self.cursor.advance_number()?;
+                let integer = !raw.contains(['.', 'e', 'E']);
+                // Integers that don't fit in 64 bits are visited as floats, like `serde_json` does
+                if let (true, Ok(value)) = (integer, raw.parse()) {
+                    visitor.visit_u64(value)
+                } else if let (true, Ok(value)) = (integer, raw.parse()) {
+                    visitor.visit_i64(value)
+                } else {
+                    match raw.parse::<f64>() {
+                        Ok(value) if value.is_finite() => visitor.visit_f64(value),
+                        _ => Err(ErrorKind::NumberOutOfRange.at(offset)),
+                    }
+                }
+            }
+            _ => Err(ErrorKind::UnexpectedValue(self.cursor.current_char()).at(offset)),
+        };
+        result.map_err(|e| e.or_offset(offset))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let offset = self.cursor.offset();
+        let result = if self.cursor.peek() == Some(b'n') {
+            self.cursor.advance_literal("null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(&mut *self)
+        };
+        result.map_err(|e| e.or_offset(offset))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let offset = self.cursor.offset();
+        visitor
+            .visit_newtype_struct(&mut *self)
+            .map_err(|e| e.or_offset(offset))
+    }
+
+    /// Enums are either a string with the name of a unit variant, or an object with a single key,
+    /// the name of the variant, and its contents as the value.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let offset = self.cursor.offset();
+        let result = match self.cursor.peek() {
+            Some(b'"') => visitor.visit_enum(UnitVariantAccess { de: self }),
+            Some(b'{') => {
+                self.cursor.advance_token();
+                let value = visitor.visit_enum(VariantAccess { de: &mut *self })?;
+                if self.cursor.peek() != Some(b'}') {
+                    return Err(ErrorKind::EnumNotSingleKey.at(offset));
+                }
+                self.cursor.advance_token();
+                Ok(value)
+            }
+            _ => Err(ErrorKind::EnumNotSingleKey.at(offset)),
+        };
+        result.map_err(|e| e.or_offset(offset))
+    }
+
+    /// Tuples must read every element of the array, as with `serde_json`.
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let offset = self.cursor.offset();
+        if self.cursor.peek() != Some(b'[') {
+            return self.deserialize_any(visitor);
+        }
+        let result = self.visit_container(|de, done| {
+            let mut seq = SeqAccess {
+                de,
+                start: offset,
+                done,
+            };
+            let value = visitor.visit_seq(&mut seq)?;
+            let mut remaining = 0;
+            while de::SeqAccess::next_element::<de::IgnoredAny>(&mut seq)?.is_some() {
+                remaining += 1;
+            }
+            if remaining > 0 {
+                return Err(de::Error::invalid_length(
+                    len + remaining,
+                    &"fewer elements in array",
+                ));
+            }
+            Ok(value)
+        });
+        result.map_err(|e| e.or_offset(offset))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    /// The value is only skipped, it is not decoded.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.cursor.advance_value()?;
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq map struct identifier
+    }
+}
+
+/// Deserializes a node, by moving a copy of its cursor through the value.
+macro_rules! forward_to_cursor {
+    ($($method:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value> {
+                let mut de = Deserializer { cursor: self.cursor };
+                de::Deserializer::$method(&mut de, $($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for GenericNode<'de> {
+    type Error = Error;
+
+    forward_to_cursor! {
+        deserialize_any(), deserialize_bool(), deserialize_i8(), deserialize_i16(),
+        deserialize_i32(), deserialize_i64(), deserialize_i128(), deserialize_u8(),
+        deserialize_u16(), deserialize_u32(), deserialize_u64(), deserialize_u128(),
+        deserialize_f32(), deserialize_f64(), deserialize_char(), deserialize_str(),
+        deserialize_string(), deserialize_bytes(), deserialize_byte_buf(), deserialize_option(),
+        deserialize_unit(), deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str), deserialize_seq(),
+        deserialize_tuple(len: usize), deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(), deserialize_ignored_any(),
+    }
+}
+
+/// Reads the entries of an object. The cursor starts after the opening brace.
+struct MapAccess<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+    /// Offset of the object, used for error reporting.
+    start: usize,
+    /// Set once the closing brace is read.
+    done: &'d mut bool,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.de.cursor.peek() {
+            None => Err(ErrorKind::UnterminatedObject.at(self.start)),
+            Some(b'}') => {
+                self.de.cursor.advance_token();
+                *self.done = true;
+                Ok(None)
+            }
+            Some(_) => self.de.key(seed).map(Some),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = seed.deserialize(&mut *self.de)?;
+        if self.de.cursor.peek() == Some(b',') {
+            self.de.cursor.advance_token();
+        }
+        Ok(value)
+    }
+}
+
+/// Reads the elements of an array. The cursor starts after the opening bracket.
+struct SeqAccess<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+    /// Offset of the array, used for error reporting.
+    start: usize,
+    /// Set once the closing bracket is read.
+    done: &'d mut bool,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        match self.de.cursor.peek() {
+            None => Err(ErrorKind::UnterminatedArray.at(self.start)),
+            Some(b']') => {
+                self.de.cursor.advance_token();
+                *self.done = true;
+                Ok(None)
+            }
+            Some(_) => {
+                let value = seed.deserialize(&mut *self.de)?;
+                if self.de.cursor.peek() == Some(b',') {
+                    self.de.cursor.advance_token();
+                }
+                Ok(Some(value))
+            }
+        }
+    }
+}
+
+/// Deserializes the key of an object entry as a string, or as an integer parsed from the text
+/// of the key.
+struct KeyDeserializer<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+}
+
+/// Parses the key as an integer and visits it with `$visit`, like `serde_json` does for maps
+/// with integer keys.
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let offset = self.de.cursor.offset();
+                let (raw, escaped) = self.de.cursor.scan_string()?;
+                self.de.cursor.skip_whitespace();
+                let key = if escaped {
+                    Cow::Owned(unescape(raw, offset + 1)?)
+                } else {
+                    Cow::Borrowed(raw)
+                };
+                match key.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(ErrorKind::InvalidNumber.at(offset)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.de.visit_string(visitor)
+    }
+
+    deserialize_integer_key! {
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64, deserialize_i128 => visit_i128, deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16, deserialize_u32 => visit_u32, deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit unit_struct newtype_struct seq
+        tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// The variant of an enum written as a string.
+struct UnitVariantAccess<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+}
+
+impl<'de> de::EnumAccess<'de> for UnitVariantAccess<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for UnitVariantAccess<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, _seed: T) -> Result<T::Value> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+/// The variant of an enum written as an object with a single key. The cursor starts at the key.
+struct VariantAccess<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = self.de.key(seed)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry<'a> {
+        symbol: &'a str,
+        name: Cow<'a, str>,
+        trade_count: u32,
+        change: f64,
+        tags: Vec<&'a str>,
+        open: Option<i8>,
+        kind: Kind,
+        #[serde(default)]
+        missing: Option<bool>,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Call,
+        Put { strike: u64 },
+        Future(bool),
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let input = r#"{"symbol": "A", "name": "a\"b", "tradeCount": 3, "change": -1e2,
+            "tags": ["x"], "open": null, "kind": "Call", "unknown": {"x": [1, true]}}"#;
+        let entry: Entry = from_str(input).unwrap();
+        assert_eq!(
+            entry,
+            Entry {
+                symbol: "A",
+                name: Cow::Owned("a\"b".to_string()),
+                trade_count: 3,
+                change: -100.0,
+                tags: vec!["x"],
+                open: None,
+                kind: Kind::Call,
+                missing: None,
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_values() {
+        assert_eq!(
+            from_str::<Vec<Kind>>(r#"[{"Put": {"strike": 5}}, {"Future": true}]"#).unwrap(),
+            [Kind::Put { strike: 5 }, Kind::Future(true)]
+        );
+        assert_eq!(
+            from_str::<HashMap<std::string::String, (i64, f64)>>(
+                r#"{"a": [-1, 18446744073709551616]}"#
+            )
+            .unwrap()["a"],
+            (-1, 18446744073709551616.0)
+        );
+        assert_eq!(
+            from_str::<Vec<Kind>>(r#"["Call", {"Call": null}]"#).unwrap(),
+            [Kind::Call, Kind::Call]
+        );
+
+        assert_eq!(
+            from_str::<HashMap<u64, i8>>(r#"{"18446744073709551615": 1, "\u0032": 2}"#).unwrap(),
+            HashMap::from([(u64::MAX, 1), (2, 2)])
+        );
+        assert_eq!(
+            from_str::<HashMap<i8, Pair>>(r#"{"-1": [1, 2]}"#).unwrap()[&-1],
+            Pair(1, 2)
+        );
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Pair(u8, u8);
+
+    #[test]
+    fn tuple_lengths() {
+        let error = from_str::<Vec<(i64,)>>("[[1], [2, 3, [4]]]").unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::Custom("invalid length 3, expected fewer elements in array".to_string())
+        );
+        assert_eq!(error.offset(), 6);
+
+        let error = from_str::<Pair>("[1, 2, 3]").unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::Custom("invalid length 3, expected fewer elements in array".to_string())
+        );
+        let error = from_str::<Pair>("[1]").unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::Custom(
+                "invalid length 1, expected tuple struct Pair with 2 elements".to_string()
+            )
+        );
+        assert_eq!(
+            from_str::<Pair>("1").unwrap_err().kind(),
+            &ErrorKind::Custom("invalid type: integer `1`, expected tuple struct Pair".to_string())
+        );
+    }
+
+    #[test]
+    fn deserialize_node() {
+        let input = r#"{"entries": [{"Put": {"strike": 5}}, "Call"], "other": 1}"#;
+        let document = Document::new(input);
+
+        let node = document.pointer("/entries/0").unwrap();
+        assert_eq!(Kind::deserialize(node).unwrap(), Kind::Put { strike: 5 });
+        let node = document.pointer("/entries").unwrap();
+        assert_eq!(
+            Vec::<Kind>::deserialize(node).unwrap(),
+            [Kind::Put { strike: 5 }, Kind::Call]
+        );
+    }
+
+    #[test]
+    fn price_change_entries() {
+        let input = include_str!("../../single.txt");
+        let serde_lazy: Vec<crate::serde_lazy::PriceChange24Hr> = from_str(input).unwrap();
+        let serde_borrowed: Vec<crate::serde_borrowed::PriceChange24Hr> = from_str(input).unwrap();
+
+        let expected = crate::serde_borrowed::parse(input).unwrap();
+        assert_eq!(serde_borrowed.len(), 1);
+        assert_eq!(
+            format!("{:?}", serde_borrowed[0]),
+            format!("{:?}", expected[0])
+        );
+        assert_eq!(
+            format!("{:?}", serde_lazy[0]),
+            format!("{:?}", crate::serde_lazy::parse(input).unwrap()[0])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input| from_str::<Entry>(input).unwrap_err();
+
+        let input = r#"{"symbol": "A"}"#;
+        assert_eq!(
+            error(input).kind(),
+            &ErrorKind::Custom("missing field `name`".to_string())
+        );
+        assert_eq!(error(input).offset(), 0);
+
+        let input = r#"{"symbol": "A", "name": "n", "tradeCount": -3}"#;
+        assert_eq!(error(input).offset(), input.find("-3").unwrap());
+
+        let input = r#"{"symbol": "a\nb"}"#;
+        assert_eq!(error(input).offset(), input.find("\"a").unwrap());
+
+        let input = r#"{"symbol": "A", "name": "n", "tradeCount": 1, "change": 1, "tags": [],
+            "open": 1, "kind": {"Call": null, "Put": null}}"#;
+        assert_eq!(error(input).kind(), &ErrorKind::EnumNotSingleKey);
+        assert_eq!(error(input).offset(), input.find("{\"Call").unwrap());
+
+        let input = r#"{"symbol": "A", "name": tru}"#;
+        assert_eq!(error(input).kind(), &ErrorKind::InvalidLiteral("true"));
+
+        let input = r#"{"1": 1, "x": 2}"#;
+        let error = from_str::<HashMap<u8, u8>>(input).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(error.offset(), input.find("\"x").unwrap());
+    }
+}
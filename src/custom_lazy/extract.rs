@@ -0,0 +1,307 @@
+//! Typed extraction of objects into user structs, generated with `#[derive(LazyExtract)]`.
+
+use std::borrow::Cow;
+
+use super::{Array, GenericNode, Object, Result};
+use crate::utils::{LazyF64, LazyU64};
+
+/// A struct that can be extracted from an `Object` in a single pass over its entries. Usually
+/// derived with `#[derive(LazyExtract)]`:
+///
+/// ```rust
+/// use binance::custom_lazy::{Document, LazyExtract};
+/// use binance::LazyF64;
+///
+/// #[derive(LazyExtract)]
+/// #[lazy(rename_all = "camelCase")]
+/// struct Ticker<'a> {
+///     symbol: &'a str,
+///     last_price: LazyF64<'a>,
+///     trade_count: u64,
+///     #[lazy(rename = "openInterest")]
+///     open_interest: Option<LazyF64<'a>>,
+/// }
+///
+/// # fn main() -> binance::custom_lazy::Result<()> {
+/// let input = r#"[{"symbol": "BTC", "lastPrice": "50", "tradeCount": 77, "volume": "1.5"}]"#;
+/// let entry = Document::new(input).as_array()?.get_index(0)?.as_object()?;
+/// let ticker = Ticker::extract(&entry)?;
+/// assert_eq!(ticker.symbol, "BTC");
+/// assert!(ticker.open_interest.is_none());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The keys are the names of the fields, unless the struct has a `#[lazy(rename_all = "...")]`
+/// attribute (`camelCase` or `PascalCase`) or the field has a `#[lazy(rename = "...")]`
+/// attribute.
+///
+/// Each field is converted with its `FromNode` implementation. A missing field is an error,
+/// unless its type is an `Option` (it becomes `None`) or it has a `#[lazy(default)]` attribute
+/// (it becomes `Default::default()`). Unknown keys are skipped, unless the struct has a
+/// `#[lazy(deny_unknown_fields)]` attribute. A key that appears twice is always an error.
+///
+/// Two fields can't have the same key:
+///
+/// ```compile_fail
+/// use binance::custom_lazy::LazyExtract;
+///
+/// #[derive(LazyExtract)]
+/// #[lazy(rename_all = "camelCase")]
+/// struct Ticker<'a> {
+///     last_price: &'a str,
+///     #[lazy(rename = "lastPrice")]
+///     price: &'a str,
+/// }
+/// ```
+pub trait LazyExtract<'a>: Sized {
+    fn extract(object: &Object<'a>) -> Result<Self>;
+}
+
+/// Conversion of the value of a field. Structs deriving `LazyExtract` implement it too, so they
+/// can be nested.
+pub trait FromNode<'a>: Sized {
+    fn from_node(node: GenericNode<'a>) -> Result<Self>;
+}
+
+impl<'a> FromNode<'a> for GenericNode<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        Ok(node)
+    }
+}
+
+impl<'a> FromNode<'a> for Object<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_object()
+    }
+}
+
+impl<'a> FromNode<'a> for Array<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_array()
+    }
+}
+
+/// The raw contents of a string, escape sequences are not decoded.
+impl<'a> FromNode<'a> for &'a str {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_string()?.get_value()
+    }
+}
+
+impl<'a> FromNode<'a> for Cow<'a, str> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_string()?.get_value_unescaped()
+    }
+}
+
+impl<'a> FromNode<'a> for std::string::String {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        Ok(node.as_string()?.get_value_unescaped()?.into_owned())
+    }
+}
+
+impl<'a> FromNode<'a> for u64 {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_number()?.as_u64()
+    }
+}
+
+impl<'a> FromNode<'a> for i64 {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_number()?.as_i64()
+    }
+}
+
+impl<'a> FromNode<'a> for f64 {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_number()?.as_f64()
+    }
+}
+
+impl<'a> FromNode<'a> for bool {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        node.as_bool()?.get_value()
+    }
+}
+
+/// Binance encodes floating point numbers as strings, so both strings and numbers are accepted.
+impl<'a> FromNode<'a> for LazyF64<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        number_or_string(node).map(LazyF64)
+    }
+}
+
+impl<'a> FromNode<'a> for LazyU64<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        number_or_string(node).map(LazyU64)
+    }
+}
+
+/// `null` becomes `None`.
+impl<'a, T: FromNode<'a>> FromNode<'a> for Option<T> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        if node.is_null() {
+            node.as_null()?.cursor.advance_value()?;
+            return Ok(None);
+        }
+        T::from_node(node).map(Some)
+    }
+}
+
+fn number_or_string(node: GenericNode<'_>) -> Result<&str> {
+    if node.cursor.peek() == Some(b'"') {
+        node.as_string()?.get_value()
+    } else {
+        node.as_number()?.raw()
+    }
+}
+
+/// Helpers used by the code generated by `#[derive(LazyExtract)]`. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use super::super::{unescape, unescaped_eq, Error, ErrorKind, GenericNode, Object, Result};
+
+    /// Returns the values of the given keys, looked up in a single pass over the object.
+    pub fn extract_fields<'a, const N: usize>(
+        object: &Object<'a>,
+        keys: &[&'static str; N],
+        deny_unknown_fields: bool,
+    ) -> Result<[Option<GenericNode<'a>>; N]> {
+        let mut result = [const { None }; N];
+        let mut iter = object.iter();
+        // The keys usually appear in the same order as the fields, so the key after the last one
+        // that was found is checked first.
+        let mut next = 0;
+
+        while let Some(entry) = iter.next_raw() {
+            let (raw_key, raw_key_offset, value) = entry?;
+
+            let mut found = None;
+            for i in (next..N).chain(0..next) {
+                if unescaped_eq(raw_key, raw_key_offset, keys[i])? {
+                    found = Some(i);
+                    break;
+                }
+            }
+
+            // Point at the opening quote of the key
+            let key_offset = raw_key_offset - 1;
+            match found {
+                Some(i) if result[i].is_some() => {
+                    return Err(ErrorKind::DuplicateField(keys[i]).at(key_offset));
+                }
+                Some(i) => {
+                    result[i] = Some(value);
+                    next = i + 1;
+                }
+                None if deny_unknown_fields => {
+                    let key = unescape(raw_key, raw_key_offset)?;
+                    return Err(ErrorKind::UnknownField(key).at(key_offset));
+                }
+                None => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn missing_field(object: &Object<'_>, key: &'static str) -> Error {
+        ErrorKind::MissingField(key).at(object.offset())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::custom_lazy::{Document, ErrorKind, LazyExtract};
+
+    #[derive(LazyExtract, Debug)]
+    #[lazy(rename_all = "camelCase")]
+    struct Entry<'a> {
+        symbol: Cow<'a, str>,
+        last_price: LazyF64<'a>,
+        open_time: LazyU64<'a>,
+        trade_count: u64,
+        #[lazy(rename = "strikePrice")]
+        strike: &'a str,
+        missing: Option<u64>,
+        #[lazy(default)]
+        defaulted: std::string::String,
+    }
+
+    #[derive(LazyExtract, Debug)]
+    #[lazy(deny_unknown_fields)]
+    struct Strict {
+        a: i64,
+        nested: Inner,
+    }
+
+    #[derive(LazyExtract, Debug, PartialEq)]
+    struct Inner {
+        r#type: bool,
+        value: Option<f64>,
+    }
+
+    #[test]
+    fn extract_entry() {
+        let input = include_str!("../../single.txt");
+        let entry = Document::new(input)
+            .as_array()
+            .unwrap()
+            .get_index(0)
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let entry = Entry::extract(&entry).unwrap();
+        assert_eq!(entry.symbol, "BTC-241206-115000-C");
+        assert_eq!(entry.last_price.0, "50");
+        assert_eq!(entry.open_time.0, "1732905664238");
+        assert_eq!(entry.trade_count, 77);
+        assert_eq!(entry.strike, "115000");
+        assert_eq!(entry.missing, None);
+        assert_eq!(entry.defaulted, "");
+    }
+
+    #[test]
+    fn nested_and_strict() {
+        let input = r#"{"nested": {"value": null, "type": true}, "a": -1}"#;
+        let strict = Strict::extract(&Document::new(input).as_object().unwrap()).unwrap();
+        assert_eq!(strict.a, -1);
+        assert_eq!(
+            strict.nested,
+            Inner {
+                r#type: true,
+                value: None
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input: &str| {
+            let object = Document::new(input).as_object().unwrap();
+            let error = Strict::extract(&object).unwrap_err();
+            (error.kind().clone(), error.offset())
+        };
+
+        assert_eq!(error(r#"{"a": 1}"#), (ErrorKind::MissingField("nested"), 0));
+        assert_eq!(
+            error(r#"{"a": 1, "b": 2}"#),
+            (ErrorKind::UnknownField("b".to_string()), 9)
+        );
+        assert_eq!(
+            error(r#"{"a": 1, "a": 2}"#),
+            (ErrorKind::DuplicateField("a"), 9)
+        );
+        assert_eq!(
+            error(r#"{"a": "1", "nested": {}}"#),
+            (ErrorKind::NotANumber, 6)
+        );
+        assert_eq!(
+            error(r#"{"a": 1, "nested": {"value": 1}}"#),
+            (ErrorKind::MissingField("type"), 19)
+        );
+    }
+}
@@ -0,0 +1,869 @@
+//! JSONPath-style queries over a lazy `Document`.
+//!
+//! A query is compiled once with `Query::parse` and can then be run over any number of
+//! documents. Running it returns an iterator that navigates the document as the results are
+//! requested: filters only look up the keys they compare, and slices stop reading the array once
+//! their end is reached.
+//!
+//! The supported syntax is a subset of JSONPath (RFC 9535):
+//!  - `$`: the root of the document, every query starts with it.
+//!  - `.name`, `['name']` or `["name"]`: the value of a key.
+//!  - `.*` or `[*]`: every element of an array or every value of an object.
+//!  - `[3]`: an element of an array. Negative indices are not supported.
+//!  - `[start:end]`: the elements from `start` (inclusive, 0 by default) to `end` (exclusive, the
+//!    end of the array by default).
+//!  - `[?(expression)]` or `[?expression]`: the elements (or values) for which the expression is
+//!    true. Expressions compare `@`-relative paths such as `@.tradeCount` or `@['a'][0]` with other
+//!    paths or with literals (numbers, strings, `true`, `false` and `null`) using `==`, `!=`, `<`,
+//!    `<=`, `>` and `>=`, and combine them with `&&`, `||`, `!` and parentheses. A path on its own
+//!    tests whether it exists.
+//!
+//! Quoted strings accept the escape sequences of JSON strings, and `\'` for a single quote.
+//!
+//! As in RFC 9535, comparisons never convert between types: `@.lastPrice > 10` is false for
+//! Binance prices, which are encoded as strings. Ordering comparisons only apply to two numbers or
+//! two strings, and comparing a missing value is only equal to another missing value. Every
+//! comparison with a number too large for an `f64` is false.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use super::{
+    decode_escape, ArrayIter, Document, ErrorKind, GenericNode, ObjectIter, Result, MAX_DEPTH,
+};
+
+/// The error returned when a query cannot be parsed. Positions are byte offsets in the query.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("Expected {expected} at position {position} of the query")]
+    Expected {
+        expected: &'static str,
+        position: usize,
+    },
+    #[error("Invalid number at position {0} of the query")]
+    InvalidNumber(usize),
+    #[error("Unterminated string at position {0} of the query")]
+    UnterminatedString(usize),
+    #[error("Invalid escape sequence at position {0} of the query")]
+    InvalidEscape(usize),
+    #[error("Filter nested too deeply at position {0} of the query")]
+    TooDeep(usize),
+}
+
+/// A compiled JSONPath query. See the module documentation for the supported syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(std::string::String),
+    Wildcard,
+    /// Selects the elements in `start..end`. An index is a slice of a single element.
+    Slice {
+        start: usize,
+        end: Option<usize>,
+    },
+    Filter(Expr),
+}
+
+/// A step of an `@`-relative path inside a filter.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(std::string::String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(Vec<Step>),
+    Compare(Operand, CompareOp, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Path(Vec<Step>),
+    Literal(Value<'static>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A value compared by a filter. Objects and arrays are compared by their raw text.
+#[derive(Debug, Clone, PartialEq)]
+enum Value<'a> {
+    Number(f64),
+    String(Cow<'a, str>),
+    Bool(bool),
+    Null,
+    Container(&'a str),
+}
+
+impl Query {
+    /// Compiles a query, such as `$[?(@.tradeCount > 0)].lastPrice`.
+    pub fn parse(query: &str) -> std::result::Result<Self, QueryError> {
+        QueryParser {
+            query,
+            pos: 0,
+            depth: 0,
+        }
+        .parse()
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Runs the query over the document. The document is only read as the results are requested.
+    ///
+    /// The iterator stops after returning the first error.
+    pub fn query<'q>(&self, query: &'q Query) -> QueryIter<'q, 'a> {
+        QueryIter {
+            segments: &query.segments,
+            stack: vec![Frame {
+                depth: 0,
+                selection: Selection::One(Some(GenericNode::from_cursor(self.cursor))),
+            }],
+        }
+    }
+}
+
+/// Iterator over the results of a query, in document order. Created with `Document::query`.
+pub struct QueryIter<'q, 'a> {
+    segments: &'q [Segment],
+    /// The selections that are being visited, from the root to the innermost one.
+    stack: Vec<Frame<'q, 'a>>,
+}
+
+/// A selection whose nodes still have to go through the segments from `depth` on.
+struct Frame<'q, 'a> {
+    depth: usize,
+    selection: Selection<'q, 'a>,
+}
+
+/// The nodes selected by a segment out of a single node.
+enum Selection<'q, 'a> {
+    One(Option<GenericNode<'a>>),
+    Elements {
+        iter: ArrayIter<'a>,
+        index: usize,
+        start: usize,
+        end: Option<usize>,
+        filter: Option<&'q Expr>,
+    },
+    Values {
+        iter: ObjectIter<'a>,
+        filter: Option<&'q Expr>,
+    },
+}
+
+impl<'a> Iterator for QueryIter<'_, 'a> {
+    type Item = Result<GenericNode<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let depth = frame.depth;
+            let node = match frame.selection.next() {
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+                Some(Ok(node)) => node,
+                Some(Err(e)) => {
+                    self.stack.clear();
+                    return Some(Err(e));
+                }
+            };
+
+            let Some(segment) = self.segments.get(depth) else {
+                return Some(Ok(node));
+            };
+            let selection = match segment.select(node) {
+                Ok(selection) => selection,
+                Err(e) => {
+                    self.stack.clear();
+                    return Some(Err(e));
+                }
+            };
+            self.stack.push(Frame {
+                depth: depth + 1,
+                selection,
+            });
+        }
+    }
+}
+
+impl std::iter::FusedIterator for QueryIter<'_, '_> {}
+
+impl Segment {
+    fn select<'q, 'a>(&'q self, node: GenericNode<'a>) -> Result<Selection<'q, 'a>> {
+        let elements = |node: GenericNode<'a>, start, end, filter| {
+            Ok(Selection::Elements {
+                iter: node.as_array()?.iter(),
+                index: 0,
+                start,
+                end,
+                filter,
+            })
+        };
+        let values = |node: GenericNode<'a>, filter| {
+            Ok(Selection::Values {
+                iter: node.as_object()?.iter(),
+                filter,
+            })
+        };
+
+        match (self, node.cursor.peek()) {
+            (Segment::Key(key), Some(b'{')) => {
+                let [value] = node.as_object()?.get_keys(&[key.as_str()])?;
+                Ok(Selection::One(value))
+            }
+            (Segment::Wildcard, Some(b'[')) => elements(node, 0, None, None),
+            (Segment::Wildcard, Some(b'{')) => values(node, None),
+            (Segment::Slice { start, end }, Some(b'[')) => elements(node, *start, *end, None),
+            (Segment::Filter(expr), Some(b'[')) => elements(node, 0, None, Some(expr)),
+            (Segment::Filter(expr), Some(b'{')) => values(node, Some(expr)),
+            _ => Ok(Selection::One(None)),
+        }
+    }
+}
+
+impl<'a> Selection<'_, 'a> {
+    fn next(&mut self) -> Option<Result<GenericNode<'a>>> {
+        match self {
+            Selection::One(node) => node.take().map(Ok),
+            Selection::Elements {
+                iter,
+                index,
+                start,
+                end,
+                filter,
+            } => loop {
+                // Stop before reading the element after the end of the slice
+                if end.is_some_and(|end| *index >= end) {
+                    return None;
+                }
+                let node = match iter.next()? {
+                    Ok(node) => node,
+                    Err(e) => return Some(Err(e)),
+                };
+                *index += 1;
+                if *index <= *start {
+                    continue;
+                }
+                match filter.map_or(Ok(true), |filter| filter.matches(&node)) {
+                    Ok(true) => return Some(Ok(node)),
+                    Ok(false) => {}
+                    Err(e) => return Some(Err(e)),
+                }
+            },
+            Selection::Values { iter, filter } => loop {
+                let node = match iter.next()? {
+                    Ok((_, node)) => node,
+                    Err(e) => return Some(Err(e)),
+                };
+                match filter.map_or(Ok(true), |filter| filter.matches(&node)) {
+                    Ok(true) => return Some(Ok(node)),
+                    Ok(false) => {}
+                    Err(e) => return Some(Err(e)),
+                }
+            },
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, node: &GenericNode<'_>) -> Result<bool> {
+        Ok(match self {
+            Expr::Or(lhs, rhs) => lhs.matches(node)? || rhs.matches(node)?,
+            Expr::And(lhs, rhs) => lhs.matches(node)? && rhs.matches(node)?,
+            Expr::Not(expr) => !expr.matches(node)?,
+            Expr::Exists(path) => resolve(node, path)?.is_some(),
+            Expr::Compare(lhs, op, rhs) => {
+                let values = lhs.value(node).and_then(|lhs| Ok((lhs, rhs.value(node)?)));
+                let (lhs, rhs) = match values {
+                    Ok(values) => values,
+                    // A number that does not fit in an `f64` only fails this comparison
+                    Err(e) if e.kind() == &ErrorKind::NumberOutOfRange => return Ok(false),
+                    Err(e) => return Err(e),
+                };
+                match op {
+                    CompareOp::Eq => lhs == rhs,
+                    CompareOp::Ne => lhs != rhs,
+                    _ => {
+                        let ordering = match (lhs, rhs) {
+                            (Some(Value::Number(a)), Some(Value::Number(b))) => a.partial_cmp(&b),
+                            (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(&b)),
+                            _ => None,
+                        };
+                        ordering.is_some_and(|ordering| match op {
+                            CompareOp::Lt => ordering == Ordering::Less,
+                            CompareOp::Le => ordering != Ordering::Greater,
+                            CompareOp::Gt => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        })
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Operand {
+    fn value<'a>(&self, node: &GenericNode<'a>) -> Result<Option<Value<'a>>> {
+        match self {
+            Operand::Literal(value) => Ok(Some(value.clone())),
+            Operand::Path(path) => resolve(node, path)?.map(Value::from_node).transpose(),
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    fn from_node(node: GenericNode<'a>) -> Result<Self> {
+        Ok(match node.cursor.peek() {
+            Some(b'"') => Value::String(node.as_string()?.get_value_unescaped()?),
+            Some(b't' | b'f') => Value::Bool(node.as_bool()?.get_value()?),
+            Some(b'n') => {
+                node.raw_slice()?;
+                Value::Null
+            }
+            Some(b'{' | b'[') => Value::Container(node.raw_slice()?),
+            _ => Value::Number(node.as_number()?.as_f64()?),
+        })
+    }
+}
+
+/// Follows an `@`-relative path, returning `None` if some step does not exist.
+fn resolve<'a>(node: &GenericNode<'a>, path: &[Step]) -> Result<Option<GenericNode<'a>>> {
+    let mut node = node.clone();
+    for step in path {
+        let next = match (step, node.cursor.peek()) {
+            (Step::Key(key), Some(b'{')) => {
+                let [value] = node.as_object()?.get_keys(&[key.as_str()])?;
+                value
+            }
+            (Step::Index(index), Some(b'[')) => node.as_array()?.iter().nth(*index).transpose()?,
+            _ => None,
+        };
+        let Some(next) = next else {
+            return Ok(None);
+        };
+        node = next;
+    }
+    Ok(Some(node))
+}
+
+/// Recursive descent parser of queries.
+struct QueryParser<'q> {
+    query: &'q str,
+    pos: usize,
+    /// Number of `!` and `(` the current expression is nested in.
+    depth: usize,
+}
+
+impl QueryParser<'_> {
+    fn parse(mut self) -> std::result::Result<Query, QueryError> {
+        self.expect("$", "`$`")?;
+
+        let mut segments = Vec::new();
+        while let Some(current) = self.peek() {
+            let segment = match current {
+                b'.' => {
+                    self.pos += 1;
+                    if self.eat("*") {
+                        Segment::Wildcard
+                    } else {
+                        Segment::Key(self.name()?.to_string())
+                    }
+                }
+                b'[' => {
+                    self.pos += 1;
+                    let segment = self.bracket()?;
+                    self.skip_whitespace();
+                    self.expect("]", "`]`")?;
+                    segment
+                }
+                _ => return Err(self.expected("`.` or `[`")),
+            };
+            segments.push(segment);
+        }
+
+        Ok(Query { segments })
+    }
+
+    /// Parses the contents of a `[...]` segment.
+    fn bracket(&mut self) -> std::result::Result<Segment, QueryError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'*') => {
+                self.pos += 1;
+                Ok(Segment::Wildcard)
+            }
+            Some(b'\'' | b'"') => Ok(Segment::Key(self.string()?)),
+            Some(b'?') => {
+                self.pos += 1;
+                Ok(Segment::Filter(self.or()?))
+            }
+            Some(b'0'..=b'9' | b':') => {
+                let position = self.pos;
+                let start = self.index()?;
+                self.skip_whitespace();
+                if !self.eat(":") {
+                    let start = start.unwrap_or_default();
+                    let end = start
+                        .checked_add(1)
+                        .ok_or(QueryError::InvalidNumber(position))?;
+                    return Ok(Segment::Slice {
+                        start,
+                        end: Some(end),
+                    });
+                }
+                self.skip_whitespace();
+                Ok(Segment::Slice {
+                    start: start.unwrap_or_default(),
+                    end: self.index()?,
+                })
+            }
+            _ => Err(self.expected("a key, an index, a slice, `*` or a filter")),
+        }
+    }
+
+    fn or(&mut self) -> std::result::Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> std::result::Result<Expr, QueryError> {
+        let mut expr = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> std::result::Result<Expr, QueryError> {
+        self.skip_whitespace();
+        let position = self.pos;
+        if self.eat("!") {
+            let expr = self.nested(position, Self::unary)?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        if self.eat("(") {
+            let expr = self.nested(position, Self::or)?;
+            self.skip_whitespace();
+            self.expect(")", "`)`")?;
+            return Ok(expr);
+        }
+
+        let lhs = self.operand()?;
+        self.skip_whitespace();
+        let Some(op) = self.compare_op() else {
+            return match lhs {
+                Operand::Path(path) => Ok(Expr::Exists(path)),
+                Operand::Literal(_) => Err(QueryError::Expected {
+                    expected: "a path",
+                    position,
+                }),
+            };
+        };
+        let rhs = self.operand()?;
+        Ok(Expr::Compare(lhs, op, rhs))
+    }
+
+    /// Parses a nested expression with `parse`, failing if it goes deeper than `MAX_DEPTH` so
+    /// that the recursion cannot overflow the stack.
+    fn nested(
+        &mut self,
+        position: usize,
+        parse: impl FnOnce(&mut Self) -> std::result::Result<Expr, QueryError>,
+    ) -> std::result::Result<Expr, QueryError> {
+        if self.depth == MAX_DEPTH {
+            return Err(QueryError::TooDeep(position));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        // Two character operators first, so `<=` is not read as `<`
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        ops.into_iter().find(|(s, _)| self.eat(s)).map(|(_, op)| op)
+    }
+
+    fn operand(&mut self) -> std::result::Result<Operand, QueryError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'@') => {
+                self.pos += 1;
+                self.path().map(Operand::Path)
+            }
+            Some(b'\'' | b'"') => Ok(Operand::Literal(Value::String(Cow::Owned(self.string()?)))),
+            Some(b'-' | b'0'..=b'9') => self.number().map(Operand::Literal),
+            _ if self.eat("true") => Ok(Operand::Literal(Value::Bool(true))),
+            _ if self.eat("false") => Ok(Operand::Literal(Value::Bool(false))),
+            _ if self.eat("null") => Ok(Operand::Literal(Value::Null)),
+            _ => Err(self.expected("`@` or a literal")),
+        }
+    }
+
+    /// Parses the steps of a path after the `@`.
+    fn path(&mut self) -> std::result::Result<Vec<Step>, QueryError> {
+        let mut path = Vec::new();
+        loop {
+            if self.eat(".") {
+                path.push(Step::Key(self.name()?.to_string()));
+            } else if self.eat("[") {
+                self.skip_whitespace();
+                let step = if matches!(self.peek(), Some(b'\'' | b'"')) {
+                    Step::Key(self.string()?)
+                } else {
+                    let index = self.index()?;
+                    Step::Index(index.ok_or_else(|| self.expected("a key or an index"))?)
+                };
+                self.skip_whitespace();
+                self.expect("]", "`]`")?;
+                path.push(step);
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    /// Parses a key in dot notation, made of alphanumeric characters and underscores.
+    fn name(&mut self) -> std::result::Result<&str, QueryError> {
+        let start = self.pos;
+        let len = self.query.as_bytes()[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count();
+        if len == 0 {
+            return Err(self.expected("a key"));
+        }
+        self.pos += len;
+        Ok(&self.query[start..self.pos])
+    }
+
+    /// Parses a quoted string. Escape sequences are decoded as in JSON strings, and `\'` is a
+    /// single quote.
+    fn string(&mut self) -> std::result::Result<std::string::String, QueryError> {
+        let start = self.pos;
+        let quote = self.query.as_bytes()[start] as char;
+        let mut value = std::string::String::new();
+        let mut pos = start + 1;
+
+        while let Some(i) = self.query[pos..].find(['\\', quote]) {
+            value.push_str(&self.query[pos..pos + i]);
+            pos += i;
+            if self.query[pos..].starts_with(quote) {
+                self.pos = pos + 1;
+                return Ok(value);
+            }
+
+            let backslash = pos;
+            pos += 1;
+            let c = if self.query[pos..].starts_with('\'') {
+                pos += 1;
+                '\''
+            } else {
+                decode_escape(self.query, &mut pos).map_err(|kind| match kind {
+                    ErrorKind::UnterminatedString => QueryError::UnterminatedString(start),
+                    _ => QueryError::InvalidEscape(backslash),
+                })?
+            };
+            value.push(c);
+        }
+
+        Err(QueryError::UnterminatedString(start))
+    }
+
+    /// Parses a non-negative array index, if there is one.
+    fn index(&mut self) -> std::result::Result<Option<usize>, QueryError> {
+        let start = self.pos;
+        let len = self.query.as_bytes()[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if len == 0 {
+            return Ok(None);
+        }
+        self.pos += len;
+        self.query[start..self.pos]
+            .parse()
+            .map(Some)
+            .map_err(|_| QueryError::InvalidNumber(start))
+    }
+
+    fn number(&mut self) -> std::result::Result<Value<'static>, QueryError> {
+        let start = self.pos;
+        let len = self.query.as_bytes()[start..]
+            .iter()
+            .take_while(|b| matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+            .count();
+        self.pos += len;
+        self.query[start..self.pos]
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| QueryError::InvalidNumber(start))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.query.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `s` if the query continues with it.
+    fn eat(&mut self, s: &str) -> bool {
+        let found = self.query[self.pos..].starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    /// Consumes `s`, or returns an error with the `expected` description.
+    fn expect(&mut self, s: &str, expected: &'static str) -> std::result::Result<(), QueryError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.expected(expected))
+        }
+    }
+
+    fn expected(&self, expected: &'static str) -> QueryError {
+        QueryError::Expected {
+            expected,
+            position: self.pos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &str = r#"[
+        {"symbol": "A", "tradeCount": 3, "lastPrice": "1.5", "tags": ["x", "y"]},
+        {"symbol": "B", "tradeCount": 0, "lastPrice": "2.5", "tags": []},
+        {"symbol": "C", "tradeCount": 7, "lastPrice": "3.5", "open": null}
+    ]"#;
+
+    fn run(query: &str) -> Vec<&'static str> {
+        let query = Query::parse(query).unwrap();
+        Document::new(INPUT)
+            .query(&query)
+            .map(|node| node.and_then(|node| node.raw_slice()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(run("$"), [INPUT.trim()]);
+        assert_eq!(run("$[*].symbol"), [r#""A""#, r#""B""#, r#""C""#]);
+        assert_eq!(run("$.*['symbol']"), [r#""A""#, r#""B""#, r#""C""#]);
+        assert_eq!(run("$[1].tradeCount"), ["0"]);
+        assert_eq!(run("$[0:2].symbol"), [r#""A""#, r#""B""#]);
+        assert_eq!(run("$[1:].symbol"), [r#""B""#, r#""C""#]);
+        assert_eq!(run("$[:1].symbol"), [r#""A""#]);
+        assert_eq!(run("$[0].tags[*]"), [r#""x""#, r#""y""#]);
+        assert_eq!(run("$[*].tags[0]"), [r#""x""#]);
+
+        // Missing keys, indices and mismatched types select nothing
+        assert!(run("$[5]").is_empty());
+        assert!(run("$[*].missing").is_empty());
+        assert!(run("$[0].symbol[0]").is_empty());
+        assert!(run("$.symbol").is_empty());
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            run("$[?(@.tradeCount > 0)].lastPrice"),
+            [r#""1.5""#, r#""3.5""#]
+        );
+        assert_eq!(run("$[?@.symbol == 'B'].tradeCount"), ["0"]);
+        assert_eq!(
+            run(r#"$[?(@.symbol != "B" && !(@.tradeCount >= 7))].symbol"#),
+            [r#""A""#]
+        );
+        assert_eq!(
+            run("$[?(@.tradeCount < 1 || @['tags'][1] == 'y')].symbol"),
+            [r#""A""#, r#""B""#]
+        );
+        assert_eq!(run("$[?(@.open)].symbol"), [r#""C""#]);
+        assert_eq!(run("$[?(@.open == null)].symbol"), [r#""C""#]);
+        // Two missing values are equal
+        assert_eq!(run("$[?(@.tags == @.tags)].symbol").len(), 3);
+        assert_eq!(run("$[?(@.tags != @.open)].symbol").len(), 3);
+
+        // No conversions between types
+        assert!(run("$[?(@.lastPrice > 1)]").is_empty());
+        assert!(run("$[?(@.tradeCount == '3')]").is_empty());
+
+        // Filters also apply to the values of objects
+        assert_eq!(run("$[0][?(@ == 3)]"), ["3"]);
+    }
+
+    #[test]
+    fn numbers_out_of_range() {
+        let input = r#"[{"a": 1e999, "b": 1}, {"a": 1, "b": 2}, {"a": -1e999, "b": 3}]"#;
+        let run = |query| {
+            let query = Query::parse(query).unwrap();
+            Document::new(input)
+                .query(&query)
+                .map(|node| node.and_then(|node| node.raw_slice()).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run("$[?(@.a == 1)].b"), ["2"]);
+        assert_eq!(run("$[?(@.a > 0)].b"), ["2"]);
+        assert_eq!(run("$[?(@.a != 1)].b"), Vec::<&str>::new());
+        assert_eq!(run("$[?!(@.a == 1)].b"), ["1", "3"]);
+        assert_eq!(run("$[?(@.a)].b"), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn escaped_strings() {
+        let input = r#"{"ab": 1, "a'b": 2, "a\"b": 3, "\u00e9\ud83d\ude00": 4, "a\nb": 5}"#;
+        let run = |query| {
+            let query = Query::parse(query).unwrap();
+            Document::new(input)
+                .query(&query)
+                .map(|node| node.and_then(|node| node.raw_slice()).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(r"$['a\u0062']"), ["1"]);
+        assert_eq!(run(r"$['a\'b']"), ["2"]);
+        assert_eq!(run(r#"$["a\"b"]"#), ["3"]);
+        assert_eq!(run(r"$['\u00E9\uD83D\uDE00']"), ["4"]);
+        assert_eq!(run(r"$['a\nb']"), ["5"]);
+
+        // Literals in filters are decoded too
+        let query = Query::parse(r"$[?@.symbol == '\u0042'].tradeCount").unwrap();
+        let results: Vec<_> = Document::new(INPUT).query(&query).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().raw_slice().unwrap(), "0");
+    }
+
+    #[test]
+    fn streaming() {
+        // The entries after the ones that are requested are never read
+        let input = r#"[{"symbol": "A", "tradeCount": 1}, {"symbol": tru"#;
+        let query = Query::parse("$[0:1].symbol").unwrap();
+        let results: Vec<_> = Document::new(input).query(&query).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().raw_slice().unwrap(), r#""A""#);
+
+        // Reading further returns the error, and then nothing else
+        let query = Query::parse("$[*].symbol").unwrap();
+        let mut results = Document::new(input).query(&query);
+        assert!(results.next().unwrap().is_ok());
+        let Some(Err(error)) = results.next() else {
+            panic!("the second entry should fail");
+        };
+        assert_eq!(error.offset(), input.find("tru").unwrap());
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |query| Query::parse(query).unwrap_err();
+
+        assert_eq!(
+            error("symbol"),
+            QueryError::Expected {
+                expected: "`$`",
+                position: 0
+            }
+        );
+        assert_eq!(
+            error("$..symbol"),
+            QueryError::Expected {
+                expected: "a key",
+                position: 2
+            }
+        );
+        assert_eq!(
+            error("$[-1]"),
+            QueryError::Expected {
+                expected: "a key, an index, a slice, `*` or a filter",
+                position: 2
+            }
+        );
+        assert_eq!(
+            error("$[0"),
+            QueryError::Expected {
+                expected: "`]`",
+                position: 3
+            }
+        );
+        assert_eq!(
+            error("$[?(@.a > 1]"),
+            QueryError::Expected {
+                expected: "`)`",
+                position: 11
+            }
+        );
+        assert_eq!(
+            error("$[?(1)]"),
+            QueryError::Expected {
+                expected: "a path",
+                position: 4
+            }
+        );
+        assert_eq!(error("$['a]"), QueryError::UnterminatedString(2));
+        assert_eq!(error("$['a\\"), QueryError::UnterminatedString(2));
+        assert_eq!(error("$['a\\x']"), QueryError::InvalidEscape(4));
+        assert_eq!(error("$['\\ud800']"), QueryError::InvalidEscape(3));
+        assert_eq!(error("$[?(@.a > 1.2.3)]"), QueryError::InvalidNumber(10));
+        assert_eq!(
+            error("$[99999999999999999999999]"),
+            QueryError::InvalidNumber(2)
+        );
+        assert_eq!(
+            error("$[18446744073709551615]"),
+            QueryError::InvalidNumber(2)
+        );
+        assert!(Query::parse("$[18446744073709551615:]").is_ok());
+
+        let nested = format!("$[?{}@.a{}]", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(Query::parse(&nested).is_ok());
+        let nested = format!("$[?{}@.a]", "!".repeat(MAX_DEPTH + 1));
+        assert_eq!(error(&nested), QueryError::TooDeep(3 + MAX_DEPTH));
+        let nested = format!("$[?{}", "!(".repeat(100_000));
+        assert_eq!(error(&nested), QueryError::TooDeep(3 + MAX_DEPTH));
+    }
+}
@@ -0,0 +1,369 @@
+//! Structural index for the lazy parser, based on stage 1 of simdjson
+//! (<https://arxiv.org/abs/1902.08318>).
+//!
+//! The index is a bitmap with one bit per byte of the input. A bit is set if the byte is a
+//! structural character (`{`, `}`, `[`, `]`, `:` or `,`) outside of a string, or if it is an
+//! unescaped quote (the start or the end of a string). With it, the cursor can skip over whole
+//! strings, objects and arrays by jumping from one structural character to the next, instead of
+//! inspecting every byte in between.
+//!
+//! The input is processed in blocks of 64 bytes. Each block is classified with SIMD instructions
+//! (AVX2 or SSE2, selected at runtime) into bitmasks of quotes, backslashes and structural
+//! characters. The remaining steps (finding escaped characters and masking out the contents of
+//! strings) only use 64-bit integer operations on those masks. A portable scalar implementation
+//! of the classification is used on other targets.
+
+const BLOCK_SIZE: usize = 64;
+
+/// Bitmasks for a block of 64 bytes. Bit `i` corresponds to byte `i` of the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BlockMasks {
+    quote: u64,
+    backslash: u64,
+    structural: u64,
+}
+
+/// The implementation used to classify the bytes in each block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Implementation {
+    // On x86_64 the scalar implementation is only used to test the SIMD ones against it
+    #[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+}
+
+impl Implementation {
+    fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Implementation::Avx2;
+            }
+            // SSE2 is part of the x86_64 baseline
+            Implementation::Sse2
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            Implementation::Scalar
+        }
+    }
+
+    fn classify(self, block: &[u8; BLOCK_SIZE]) -> BlockMasks {
+        match self {
+            Implementation::Scalar => classify_scalar(block),
+            // SAFETY: SSE2 is always available on x86_64.
+            #[cfg(target_arch = "x86_64")]
+            Implementation::Sse2 => unsafe { x86::classify_sse2(block) },
+            // SAFETY: `Avx2` is only selected after detecting AVX2 support at runtime.
+            #[cfg(target_arch = "x86_64")]
+            Implementation::Avx2 => unsafe { x86::classify_avx2(block) },
+        }
+    }
+}
+
+fn classify_scalar(block: &[u8; BLOCK_SIZE]) -> BlockMasks {
+    let mut masks = BlockMasks {
+        quote: 0,
+        backslash: 0,
+        structural: 0,
+    };
+    for (i, &b) in block.iter().enumerate() {
+        let bit = 1 << i;
+        match b {
+            b'"' => masks.quote |= bit,
+            b'\\' => masks.backslash |= bit,
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => masks.structural |= bit,
+            _ => {}
+        }
+    }
+    masks
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{BlockMasks, BLOCK_SIZE};
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn classify_sse2(block: &[u8; BLOCK_SIZE]) -> BlockMasks {
+        let mut masks = BlockMasks {
+            quote: 0,
+            backslash: 0,
+            structural: 0,
+        };
+        for i in 0..BLOCK_SIZE / 16 {
+            let chunk = _mm_loadu_si128(block.as_ptr().add(i * 16) as *const __m128i);
+            let eq = |v: __m128i, c: u8| {
+                _mm_movemask_epi8(_mm_cmpeq_epi8(v, _mm_set1_epi8(c as i8))) as u16 as u64
+            };
+            // `[` and `]` only differ from `{` and `}` in the 0x20 bit, and no other character
+            // becomes `{` or `}` when setting it.
+            let lowered = _mm_or_si128(chunk, _mm_set1_epi8(0x20));
+            let shift = i * 16;
+            masks.quote |= eq(chunk, b'"') << shift;
+            masks.backslash |= eq(chunk, b'\\') << shift;
+            masks.structural |=
+                (eq(lowered, b'{') | eq(lowered, b'}') | eq(chunk, b':') | eq(chunk, b','))
+                    << shift;
+        }
+        masks
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn classify_avx2(block: &[u8; BLOCK_SIZE]) -> BlockMasks {
+        let mut masks = BlockMasks {
+            quote: 0,
+            backslash: 0,
+            structural: 0,
+        };
+        for i in 0..BLOCK_SIZE / 32 {
+            let chunk = _mm256_loadu_si256(block.as_ptr().add(i * 32) as *const __m256i);
+            let eq = |v: __m256i, c: u8| {
+                _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, _mm256_set1_epi8(c as i8))) as u32 as u64
+            };
+            let lowered = _mm256_or_si256(chunk, _mm256_set1_epi8(0x20));
+            let shift = i * 32;
+            masks.quote |= eq(chunk, b'"') << shift;
+            masks.backslash |= eq(chunk, b'\\') << shift;
+            masks.structural |=
+                (eq(lowered, b'{') | eq(lowered, b'}') | eq(chunk, b':') | eq(chunk, b','))
+                    << shift;
+        }
+        masks
+    }
+}
+
+/// Returns a mask of the characters escaped by a backslash. `prev_escaped` carries whether the
+/// first character of the next block is escaped.
+///
+/// This is the branchless algorithm from simdjson: a run of backslashes escapes the next character
+/// if it has an odd length.
+fn find_escaped(backslash: u64, prev_escaped: &mut u64) -> u64 {
+    const EVEN_BITS: u64 = 0x5555_5555_5555_5555;
+
+    let backslash = backslash & !*prev_escaped;
+    let follows_escape = (backslash << 1) | *prev_escaped;
+    let odd_sequence_starts = backslash & !EVEN_BITS & !follows_escape;
+    let (sequences_starting_on_even_bits, overflow) =
+        odd_sequence_starts.overflowing_add(backslash);
+    *prev_escaped = overflow as u64;
+    let invert_mask = sequences_starting_on_even_bits << 1;
+    (EVEN_BITS ^ invert_mask) & follows_escape
+}
+
+/// Computes the prefix xor of the bits of `x`: bit `i` of the result is the xor of bits `0..=i`.
+/// Applied to the quote mask, this gives the mask of the bytes inside strings.
+fn prefix_xor(mut x: u64) -> u64 {
+    x ^= x << 1;
+    x ^= x << 2;
+    x ^= x << 4;
+    x ^= x << 8;
+    x ^= x << 16;
+    x ^= x << 32;
+    x
+}
+
+/// A bitmap of the structural characters of a JSON document. See the module documentation.
+///
+/// Build it with `StructuralIndex::new` and pass it to `Document::with_index`.
+pub struct StructuralIndex {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl StructuralIndex {
+    /// Builds the index for the given data, using the fastest implementation supported by the
+    /// CPU.
+    pub fn new(data: &str) -> Self {
+        Self::build(data.as_bytes(), Implementation::detect())
+    }
+
+    fn build(data: &[u8], implementation: Implementation) -> Self {
+        let mut bits = Vec::with_capacity(data.len().div_ceil(BLOCK_SIZE));
+        let mut prev_escaped = 0;
+        let mut prev_in_string = 0;
+
+        let mut process = |block: &[u8; BLOCK_SIZE]| {
+            let masks = implementation.classify(block);
+            let escaped = find_escaped(masks.backslash, &mut prev_escaped);
+            let quotes = masks.quote & !escaped;
+            let in_string = prefix_xor(quotes) ^ prev_in_string;
+            // Broadcast the last bit to carry it to the next block
+            prev_in_string = 0u64.wrapping_sub(in_string >> 63);
+            bits.push((masks.structural & !in_string) | quotes);
+        };
+
+        let mut chunks = data.chunks_exact(BLOCK_SIZE);
+        for chunk in &mut chunks {
+            process(
+                chunk
+                    .try_into()
+                    .expect("Chunks have exactly BLOCK_SIZE bytes"),
+            );
+        }
+
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            let mut block = [b' '; BLOCK_SIZE];
+            block[..remainder.len()].copy_from_slice(remainder);
+            process(&block);
+        }
+
+        Self {
+            bits,
+            len: data.len(),
+        }
+    }
+
+    /// Length of the data the index was built for.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the index was built for empty data.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the positions of the structural characters at or after `from`.
+    pub(crate) fn structurals_from(&self, from: usize) -> StructuralIter<'_> {
+        let word = from / BLOCK_SIZE;
+        let current = self
+            .bits
+            .get(word)
+            .map_or(0, |bits| bits & (u64::MAX << (from % BLOCK_SIZE)));
+        StructuralIter {
+            bits: &self.bits,
+            word,
+            current,
+        }
+    }
+}
+
+/// Iterator over the positions of the bits set in a `StructuralIndex`.
+pub(crate) struct StructuralIter<'a> {
+    bits: &'a [u64],
+    word: usize,
+    current: u64,
+}
+
+impl Iterator for StructuralIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.word += 1;
+            self.current = *self.bits.get(self.word)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.word * BLOCK_SIZE + bit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Straightforward byte by byte implementation of the index, used as reference.
+    ///
+    /// As in the bitmask implementation, a backslash escapes the next character even outside of
+    /// strings, which can only happen in invalid JSON.
+    fn reference_index(data: &[u8]) -> Vec<usize> {
+        let mut result = vec![];
+        let mut in_string = false;
+        let mut escaped = false;
+        for (i, &b) in data.iter().enumerate() {
+            let is_escaped = escaped;
+            escaped = b == b'\\' && !is_escaped;
+
+            if b == b'"' && !is_escaped {
+                in_string = !in_string;
+                result.push(i);
+            } else if !in_string && matches!(b, b'{' | b'}' | b'[' | b']' | b':' | b',') {
+                result.push(i);
+            }
+        }
+        result
+    }
+
+    fn implementations() -> Vec<Implementation> {
+        let mut implementations = vec![Implementation::Scalar];
+        #[cfg(target_arch = "x86_64")]
+        {
+            implementations.push(Implementation::Sse2);
+            if is_x86_feature_detected!("avx2") {
+                implementations.push(Implementation::Avx2);
+            }
+        }
+        implementations
+    }
+
+    /// Generates pseudo-random data that is rich in the characters relevant to the index.
+    fn random_data(seed: u64, len: usize) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"\"\\{}[]:, a1{[\"\\\\";
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ALPHABET[(state >> 33) as usize % ALPHABET.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn implementations_agree() {
+        for seed in 0..64 {
+            let data = random_data(seed, 64);
+            let block: &[u8; BLOCK_SIZE] = data.as_slice().try_into().unwrap();
+            let expected = classify_scalar(block);
+            for implementation in implementations() {
+                assert_eq!(
+                    implementation.classify(block),
+                    expected,
+                    "{implementation:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_reference() {
+        let inputs: Vec<Vec<u8>> = [
+            &br#"{"a": [1, "x,y", {"b\"": "}]"}], "c": "\\", "d": "\\\"{"}"#[..],
+            br#""\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\"[]""#,
+            include_bytes!("../../single.txt"),
+        ]
+        .into_iter()
+        .map(|input| input.to_vec())
+        .chain((0..32).map(|seed| random_data(seed, 1000)))
+        .collect();
+
+        for input in inputs {
+            let expected = reference_index(&input);
+            for implementation in implementations() {
+                let index = StructuralIndex::build(&input, implementation);
+                let actual: Vec<_> = index.structurals_from(0).collect();
+                assert_eq!(actual, expected, "{implementation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn structurals_from() {
+        let data = format!("{}[{}]", " ".repeat(70), " ".repeat(60));
+        let index = StructuralIndex::new(&data);
+
+        assert_eq!(index.len(), data.len());
+        assert_eq!(index.structurals_from(0).collect::<Vec<_>>(), [70, 131]);
+        assert_eq!(index.structurals_from(70).collect::<Vec<_>>(), [70, 131]);
+        assert_eq!(index.structurals_from(71).collect::<Vec<_>>(), [131]);
+        assert_eq!(index.structurals_from(132).count(), 0);
+        assert_eq!(index.structurals_from(1000).count(), 0);
+    }
+}
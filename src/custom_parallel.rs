@@ -0,0 +1,175 @@
+use std::ops::Range;
+use std::thread;
+
+use crate::custom::{Error, PriceChange24Hr};
+use crate::custom_stream::{parse_entry, split_entries};
+
+/// Below this number of entries per thread, spawning a thread costs more than it saves.
+const MIN_ENTRIES_PER_THREAD: usize = 64;
+
+/// Parses the data returned by the `GET /eapi/v1/ticker` endpoint using up to `threads` threads,
+/// including the current one. The entries are returned in the same order as in the input.
+///
+/// The structure of the top-level array is checked before any entry is parsed. If several entries
+/// are invalid, the error of the first one is returned.
+pub fn parse_parallel(s: &str, threads: usize) -> Result<Vec<PriceChange24Hr<'_>>, Error> {
+    let entries = split_entries(s)?;
+    let parse_batch = |batch: &[Range<usize>]| -> Result<Vec<_>, Error> {
+        batch
+            .iter()
+            .map(|range| parse_entry(&s[range.clone()], range.start))
+            .collect()
+    };
+
+    let batch_size = entries
+        .len()
+        .div_ceil(threads.max(1))
+        .max(MIN_ENTRIES_PER_THREAD);
+    if batch_size >= entries.len() {
+        return parse_batch(&entries);
+    }
+
+    thread::scope(|scope| {
+        let mut batches = entries.chunks(batch_size);
+        let first = batches.next().unwrap_or_default();
+        let handles: Vec<_> = batches
+            .map(|batch| scope.spawn(move || parse_batch(batch)))
+            .collect();
+
+        // The current thread parses the first batch instead of waiting for the others
+        let mut result = Vec::with_capacity(entries.len());
+        result.extend(parse_batch(first)?);
+        for handle in handles {
+            let batch = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+            result.extend(batch);
+        }
+        Ok(result)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::custom::ErrorKind;
+
+    #[test]
+    fn same_as_custom() {
+        let input = std::fs::read_to_string("request.txt").unwrap();
+        let expected = format!("{:?}", crate::custom::parse(&input).unwrap());
+
+        for threads in [0, 1, 2, 3, 8, 2000] {
+            let entries = parse_parallel(&input, threads).unwrap();
+            assert_eq!(format!("{entries:?}"), expected, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn small_inputs() {
+        assert_eq!(parse_parallel("[]", 4).unwrap().len(), 0);
+
+        let single = std::fs::read_to_string("single.txt").unwrap();
+        let entries = parse_parallel(&single, 4).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].symbol, "BTC-241206-115000-C");
+    }
+
+    #[test]
+    fn first_error() {
+        let input = std::fs::read_to_string("request.txt").unwrap();
+        // Break an entry in the middle and one close to the end, in different batches
+        let middle = input.len() / 2 + input[input.len() / 2..].find("\"tradeCount\"").unwrap();
+        let last = input.rfind("\"tradeCount\"").unwrap();
+        let mut broken = input.clone();
+        broken.replace_range(last + 1..last + 6, "Trade");
+        broken.replace_range(middle + 1..middle + 6, "Trade");
+
+        let error = parse_parallel(&broken, 4).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnknownField("TradeCount".into()));
+        assert_eq!(error.offset(), middle);
+
+        let expected = crate::custom::parse(&broken).unwrap_err();
+        assert_eq!(
+            (error.kind(), error.offset()),
+            (expected.kind(), expected.offset())
+        );
+
+        let error = parse_parallel(&input[..input.rfind('}').unwrap()], 4).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnterminatedJsonObject);
+    }
+
+    #[test]
+    fn random_splits() {
+        let single = std::fs::read_to_string("single.txt").unwrap();
+        let entry = single.trim().trim_start_matches('[').trim_end_matches(']');
+        let mut state = 0x9e3779b9_u64;
+        let mut below = |n: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % n as u64) as usize
+        };
+
+        // Enough entries to give every thread a batch, with random values and whitespace
+        for threads in 2..=4 {
+            let min_entries = MIN_ENTRIES_PER_THREAD * threads;
+            let count = min_entries + below(min_entries);
+            let mut input = String::from("[");
+            for i in 0..count {
+                if i > 0 {
+                    input.push(',');
+                }
+                input.push_str(["", " ", "\n", "\t", " \r\n "][below(5)]);
+                let random_entry = entry
+                    .replace("BTC-241206-115000-C", &format!("BTC-{i}-C"))
+                    .replace(
+                        "\"tradeCount\":77",
+                        &format!("\"tradeCount\":{}", below(1000)),
+                    )
+                    .replace(
+                        "\"lastPrice\":\"50\"",
+                        &format!("\"lastPrice\":\"{}\"", below(99)),
+                    );
+                input.push_str(&random_entry);
+            }
+            input.push(']');
+
+            let entries = parse_parallel(&input, threads).unwrap();
+            assert_eq!(entries.len(), count);
+            let expected = crate::custom::parse(&input).unwrap();
+            assert_eq!(format!("{entries:?}"), format!("{expected:?}"), "{threads}");
+
+            // Break a random entry, which may be in any of the batches
+            let symbol = format!("\"BTC-{}-C\",\"p", below(count));
+            let broken = input.replacen(&symbol, &symbol.replace(",\"p", ",\"P"), 1);
+            let error = parse_parallel(&broken, threads).unwrap_err();
+            let expected = crate::custom::parse(&broken).unwrap_err();
+            assert_eq!(
+                (error.kind(), error.offset()),
+                (expected.kind(), expected.offset())
+            );
+        }
+    }
+
+    #[test]
+    fn structure_errors() {
+        let cases = [
+            ("", ErrorKind::EmptyJson, 0),
+            (" {}", ErrorKind::NotAJsonArray, 1),
+            ("[{}, 1]", ErrorKind::NotAJsonObject, 5),
+            (r#"[{"a": "}]"}, x]"#, ErrorKind::NotAJsonObject, 14),
+            (r#"[{"a": "}]"} x]"#, ErrorKind::MalformedJsonArray, 13),
+            (r#"[{"a": "\\"}] ,"#, ErrorKind::UnexpectedToken, 14),
+            (r#"[{}, {"a": "\"}]"#, ErrorKind::UnterminatedJsonObject, 5),
+            // Form feed is whitespace for `u8::is_ascii_whitespace`, but not for JSON
+            ("[\x0c]", ErrorKind::NotAJsonObject, 1),
+            ("\x0c[]", ErrorKind::NotAJsonArray, 0),
+        ];
+        for (input, kind, offset) in cases {
+            let error = parse_parallel(input, 4).unwrap_err();
+            assert_eq!((error.kind(), error.offset()), (&kind, offset), "{input}");
+            assert!(crate::custom::parse(input).is_err(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use std::ops::Range;
+
+use crate::custom::{parse_json, parse_price_change_entry, Error, ErrorKind, PriceChange24Hr};
+use crate::custom_lazy::StructuralIndex;
+use crate::utils::is_json_whitespace;
+
+/// Where the scanner is in the top-level array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the opening bracket of the array.
+    Start,
+    /// Expecting an entry, or the end of the array if `first` is set.
+    BeforeEntry { first: bool },
+    /// Inside an entry.
+    InEntry,
+    /// After an entry, expecting a comma or the end of the array.
+    AfterEntry,
+    /// After the closing bracket of the array.
+    End,
+}
+
+/// Tracks the structure of the top-level array, byte by byte, to find where each entry starts and
+/// ends. Brackets are only counted to find the end of an entry, the entry is validated when it is
+/// parsed.
+#[derive(Debug)]
+struct Scanner {
+    state: State,
+    /// Start of the current entry.
+    entry_start: usize,
+    /// Nesting depth inside the current entry.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Entries completed since the last call to `clear`.
+    ready: Vec<Range<usize>>,
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Self {
+            state: State::Start,
+            entry_start: 0,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            ready: Vec::new(),
+        }
+    }
+
+    /// Scans `input` from `from`, recording the ranges of the entries that are complete. `base`
+    /// is the offset of `input` in the whole body, used for the errors. If the structural index
+    /// of `input` is given, it is used to skip over the entries.
+    fn scan(
+        &mut self,
+        input: &[u8],
+        from: usize,
+        base: usize,
+        index: Option<&StructuralIndex>,
+    ) -> Result<(), Error> {
+        let mut pos = from;
+        while pos < input.len() {
+            if self.state == State::InEntry {
+                pos = match index {
+                    Some(index) => self.skip_entry(input, index, pos),
+                    None => self.scan_entry(input, pos),
+                };
+                continue;
+            }
+
+            let byte = input[pos];
+            pos += 1;
+            if is_json_whitespace(byte) {
+                continue;
+            }
+
+            let offset = base + pos - 1;
+            self.state = match (self.state, byte) {
+                (State::Start, b'[') => State::BeforeEntry { first: true },
+                (State::Start, _) => return Err(ErrorKind::NotAJsonArray.at(offset)),
+                (State::BeforeEntry { .. }, b'{') => {
+                    self.entry_start = pos - 1;
+                    self.depth = 1;
+                    State::InEntry
+                }
+                (State::BeforeEntry { first: true }, b']') => State::End,
+                (State::BeforeEntry { .. }, b']' | b',') => {
+                    return Err(ErrorKind::MalformedJsonArray.at(offset))
+                }
+                (State::BeforeEntry { .. }, _) => return Err(ErrorKind::NotAJsonObject.at(offset)),
+                (State::AfterEntry, b',') => State::BeforeEntry { first: false },
+                (State::AfterEntry, b']') => State::End,
+                (State::AfterEntry, _) => return Err(ErrorKind::MalformedJsonArray.at(offset)),
+                (State::End, _) | (State::InEntry, _) => {
+                    return Err(ErrorKind::UnexpectedToken.at(offset))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Scans the current entry from `pos` until it ends or the input ends, and returns the
+    /// position after the last byte scanned. Jumps straight to the bytes that can change the
+    /// state, which are quotes and backslashes inside strings and quotes and brackets outside.
+    fn scan_entry(&mut self, input: &[u8], mut pos: usize) -> usize {
+        while pos < input.len() {
+            if self.escaped {
+                self.escaped = false;
+                pos += 1;
+                continue;
+            }
+
+            let found = if self.in_string {
+                input[pos..].iter().position(|&b| b == b'"' || b == b'\\')
+            } else {
+                input[pos..]
+                    .iter()
+                    .position(|&b| matches!(b, b'"' | b'{' | b'}' | b'[' | b']'))
+            };
+            let Some(found) = found else {
+                return input.len();
+            };
+            pos += found;
+
+            match input[pos] {
+                b'\\' => self.escaped = true,
+                b'"' => self.in_string = !self.in_string,
+                byte => {
+                    if self.bracket(byte, pos) {
+                        return pos + 1;
+                    }
+                }
+            }
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Same as `scan_entry`, but only visits the structural characters of the index, which
+    /// already excludes the contents of strings.
+    fn skip_entry(&mut self, input: &[u8], index: &StructuralIndex, pos: usize) -> usize {
+        for pos in index.structurals_from(pos) {
+            if self.bracket(input[pos], pos) {
+                return pos + 1;
+            }
+        }
+        input.len()
+    }
+
+    /// Updates the depth for a byte of an entry outside of strings. Returns true if it closed the
+    /// entry.
+    fn bracket(&mut self, byte: u8, pos: usize) -> bool {
+        match byte {
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' => {
+                self.depth -= 1;
+                if self.depth == 0 {
+                    self.ready.push(self.entry_start..pos + 1);
+                    self.state = State::AfterEntry;
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Checks that the array is complete. `end` is the offset of the end of the body.
+    fn finish(&self, base: usize, end: usize) -> Result<(), Error> {
+        match self.state {
+            State::End => Ok(()),
+            State::Start => Err(ErrorKind::EmptyJson.at(end)),
+            State::InEntry => Err(ErrorKind::UnterminatedJsonObject.at(base + self.entry_start)),
+            State::BeforeEntry { .. } | State::AfterEntry => {
+                Err(ErrorKind::UnterminatedJsonArray.at(end))
+            }
+        }
+    }
+
+    /// Forgets the entries that were completed and moves the start of the current entry, after
+    /// `by` bytes were dropped from the start of the input.
+    fn clear(&mut self, by: usize) {
+        self.ready.clear();
+        self.entry_start -= by.min(self.entry_start);
+    }
+}
+
+/// Returns the ranges of the entries of a complete body, using a structural index to skip over
+/// the entries.
+pub(crate) fn split_entries(s: &str) -> Result<Vec<Range<usize>>, Error> {
+    let index = StructuralIndex::new(s);
+    let mut scanner = Scanner::new();
+    scanner.scan(s.as_bytes(), 0, 0, Some(&index))?;
+    scanner.finish(0, s.len())?;
+    Ok(scanner.ready)
+}
+
+/// Parses an entry that starts at `start` in the whole body.
+pub(crate) fn parse_entry(entry: &str, start: usize) -> Result<PriceChange24Hr<'_>, Error> {
+    // Offsets of the errors are relative to the entry, make them relative to the whole body
+    parse_json(entry)
+        .and_then(|ast| parse_price_change_entry(entry, &ast.root()))
+        .map_err(|e| e.shifted(start))
+}
+
+/// A push parser for the data returned by the `GET /eapi/v1/ticker` endpoint.
+///
+/// The body is pushed in chunks of any size as it arrives, and every call to `push` returns the
+/// entries that were completed by that chunk. Only the bytes of the entry that is not complete
+/// yet are kept between calls.
+#[derive(Debug)]
+pub struct Parser {
+    buffer: Vec<u8>,
+    /// Offset in the whole body of the first byte of `buffer`.
+    base: usize,
+    scanner: Scanner,
+    /// The error that stopped the parser, returned again by any later call.
+    error: Option<Error>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            base: 0,
+            scanner: Scanner::new(),
+            error: None,
+        }
+    }
+
+    /// Feeds the next chunk of the body and returns the entries that it completed. An entry can
+    /// span any number of chunks, and chunks may split multi-byte characters.
+    ///
+    /// The entries borrow from the parser, so they must be dropped before the next chunk is
+    /// pushed. After an error, every later call returns the same error.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Entries<'_>, Error> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        // The entries returned by the previous call are no longer borrowed, drop their bytes
+        let keep_from = match self.scanner.state {
+            State::InEntry => self.scanner.entry_start,
+            _ => self.buffer.len(),
+        };
+        self.buffer.drain(..keep_from);
+        self.base += keep_from;
+        self.scanner.clear(keep_from);
+
+        let scanned = self.buffer.len();
+        self.buffer.extend_from_slice(chunk);
+        if let Err(error) = self.scanner.scan(&self.buffer, scanned, self.base, None) {
+            self.error = Some(error.clone());
+            return Err(error);
+        }
+
+        Ok(Entries {
+            buffer: &self.buffer,
+            base: self.base,
+            ranges: self.scanner.ready.iter(),
+        })
+    }
+
+    /// Checks that the body ended with a complete array. Must be called after the last chunk.
+    pub fn finish(&self) -> Result<(), Error> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.scanner
+            .finish(self.base, self.base + self.buffer.len())
+    }
+}
+
+/// Iterator over the entries completed by a chunk. Created with `Parser::push`.
+#[derive(Debug)]
+pub struct Entries<'p> {
+    buffer: &'p [u8],
+    base: usize,
+    ranges: std::slice::Iter<'p, Range<usize>>,
+}
+
+impl<'p> Iterator for Entries<'p> {
+    type Item = Result<PriceChange24Hr<'p>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next()?.clone();
+        let start = self.base + range.start;
+        let result = match std::str::from_utf8(&self.buffer[range]) {
+            Ok(entry) => parse_entry(entry, start),
+            Err(e) => Err(ErrorKind::InvalidUtf8.at(start + e.valid_up_to())),
+        };
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ranges.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Entries<'_> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Pushes the input in chunks of the given size, returning the symbols of the entries.
+    fn push_in_chunks(input: &[u8], size: usize) -> Result<Vec<String>, Error> {
+        let mut parser = Parser::new();
+        let mut symbols = Vec::new();
+        for chunk in input.chunks(size) {
+            for entry in parser.push(chunk)? {
+                symbols.push(entry?.symbol.to_string());
+            }
+        }
+        parser.finish()?;
+        Ok(symbols)
+    }
+
+    #[test]
+    fn every_chunk_size() {
+        let input = std::fs::read_to_string("request.txt").unwrap();
+        let expected: Vec<_> = crate::custom::parse(&input)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.symbol.to_string())
+            .collect();
+
+        for size in [1, 7, 64, 1000, input.len()] {
+            let symbols = push_in_chunks(input.as_bytes(), size).unwrap();
+            assert_eq!(symbols, expected, "chunks of {size} bytes");
+        }
+    }
+
+    #[test]
+    fn entries_as_soon_as_they_close() {
+        let single = std::fs::read_to_string("single.txt").unwrap();
+        let entry = single.trim().trim_start_matches('[').trim_end_matches(']');
+        // A multi-byte character, split between chunks, and brackets inside strings
+        let entry = entry.replace("BTC-", "BTC-ü{[\\\"");
+        let input = format!(" [{entry},\n{entry}] ");
+        let first_end = input.find("},").unwrap() + 1;
+
+        let mut parser = Parser::new();
+        assert_eq!(
+            parser
+                .push(input[..first_end - 1].as_bytes())
+                .unwrap()
+                .len(),
+            0
+        );
+        let entries: Vec<_> = parser
+            .push(&input.as_bytes()[first_end - 1..first_end])
+            .unwrap()
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].as_ref().unwrap().symbol,
+            "BTC-ü{[\\\"241206-115000-C"
+        );
+
+        let u = input.rfind('ü').unwrap();
+        assert_eq!(
+            parser
+                .push(&input.as_bytes()[first_end..u + 1])
+                .unwrap()
+                .len(),
+            0
+        );
+        assert_eq!(parser.push(&input.as_bytes()[u + 1..]).unwrap().len(), 1);
+        parser.finish().unwrap();
+    }
+
+    #[test]
+    fn empty_array() {
+        assert_eq!(push_in_chunks(b" [ ] ", 1).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn errors() {
+        let single = std::fs::read_to_string("single.txt").unwrap();
+        let entry = single.trim().trim_start_matches('[').trim_end_matches(']');
+
+        let cases = [
+            ("".to_string(), ErrorKind::EmptyJson, 0),
+            ("{}".to_string(), ErrorKind::NotAJsonArray, 0),
+            ("[1]".to_string(), ErrorKind::NotAJsonObject, 1),
+            ("[,]".to_string(), ErrorKind::MalformedJsonArray, 1),
+            ("[\x0c]".to_string(), ErrorKind::NotAJsonObject, 1),
+            (
+                format!("[{entry}\x0c]"),
+                ErrorKind::MalformedJsonArray,
+                entry.len() + 1,
+            ),
+            (
+                format!("[{entry},]"),
+                ErrorKind::MalformedJsonArray,
+                entry.len() + 2,
+            ),
+            (
+                format!("[{entry} {entry}]"),
+                ErrorKind::MalformedJsonArray,
+                entry.len() + 2,
+            ),
+            (
+                format!("[{entry}] x"),
+                ErrorKind::UnexpectedToken,
+                entry.len() + 3,
+            ),
+            (
+                format!("[{entry}"),
+                ErrorKind::UnterminatedJsonArray,
+                entry.len() + 1,
+            ),
+            (
+                format!("[{entry}, {{\"a"),
+                ErrorKind::UnterminatedJsonObject,
+                entry.len() + 3,
+            ),
+            (
+                format!("[{entry}, {{\"a\": 1}}]"),
+                ErrorKind::UnknownField("a".to_string()),
+                entry.len() + 4,
+            ),
+            (
+                format!("[{entry}, {}]", entry.replace("\"-45\"", "-4x")),
+                ErrorKind::MalformedJsonObject,
+                entry.len() + 3 + entry.find("\"-45\"").unwrap() + 2,
+            ),
+        ];
+
+        for (input, kind, offset) in cases {
+            for size in [1, 5, input.len().max(1)] {
+                let error = push_in_chunks(input.as_bytes(), size).unwrap_err();
+                assert_eq!((error.kind(), error.offset()), (&kind, offset), "{input}");
+            }
+        }
+
+        let mut parser = Parser::new();
+        let error = parser.push(b"[\xff").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::NotAJsonObject);
+        assert!(parser.push(b"[]").is_err());
+        assert!(parser.finish().is_err());
+
+        let mut parser = Parser::new();
+        let entries: Vec<_> = parser.push(b"[{\"a\xff\": 1}").unwrap().collect();
+        let error = entries[0].as_ref().unwrap_err();
+        assert_eq!((error.kind(), error.offset()), (&ErrorKind::InvalidUtf8, 4));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Display;
+
+/// Maximum number of characters shown on each side of the error in a snippet. Binance returns the
+/// whole response in a single line, so showing the full line is not an option.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A position in the input, as a line and a column. Both are 1-based, and the column is counted in
+/// characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Computes the location of the given byte offset. Offsets past the end of the input point
+    /// right after its last character.
+    pub fn from_offset(input: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(input, offset);
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Returns the line of the input that contains the given byte offset, followed by a line with a
+/// caret pointing at the offset. Long lines are shortened around the offset.
+pub fn snippet(input: &str, offset: usize) -> String {
+    let offset = floor_char_boundary(input, offset);
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[offset..]
+        .find('\n')
+        .map_or(input.len(), |i| offset + i);
+
+    let start = input[line_start..offset]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(line_start, |(i, _)| line_start + i);
+    let end = input[offset..line_end]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT + 1)
+        .map_or(line_end, |(i, _)| offset + i);
+
+    let prefix = if start > line_start { "..." } else { "" };
+    let suffix = if end < line_end { "..." } else { "" };
+    let caret_column = prefix.len() + input[start..offset].chars().count();
+
+    format!(
+        "{prefix}{}{suffix}\n{}^",
+        input[start..end].trim_end_matches('\r'),
+        " ".repeat(caret_column)
+    )
+}
+
+/// Formats an error message together with its location and a snippet of the input.
+pub fn report(message: impl Display, input: &str, offset: usize) -> String {
+    format!(
+        "{message} at {}\n{}",
+        Location::from_offset(input, offset),
+        snippet(input, offset)
+    )
+}
+
+fn floor_char_boundary(input: &str, offset: usize) -> usize {
+    let mut offset = offset.min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn location() {
+        let input = "[\n  {\"a\": 1},\n  {\"ü\": x}\n]";
+
+        assert_eq!(
+            Location::from_offset(input, 0),
+            Location { line: 1, column: 1 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 1),
+            Location { line: 1, column: 2 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 2),
+            Location { line: 2, column: 1 }
+        );
+
+        let x = input.find('x').unwrap();
+        assert_eq!(
+            Location::from_offset(input, x),
+            Location { line: 3, column: 9 }
+        );
+        // Offsets inside a character point at the character
+        let u = input.find('ü').unwrap();
+        assert_eq!(
+            Location::from_offset(input, u + 1),
+            Location { line: 3, column: 5 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 1000),
+            Location { line: 4, column: 2 }
+        );
+    }
+
+    #[test]
+    fn short_snippet() {
+        let input = "[\n  {\"ü\": x}\n]";
+        let x = input.find('x').unwrap();
+
+        assert_eq!(snippet(input, x), "  {\"ü\": x}\n        ^");
+        assert_eq!(snippet(input, input.len()), "]\n ^");
+    }
+
+    #[test]
+    fn long_line_snippet() {
+        let input = format!("[{}x{}]", "1,".repeat(100), ",2".repeat(100));
+        let x = input.find('x').unwrap();
+
+        let expected = format!(
+            "...{}x{}...\n{}^",
+            &"1,".repeat(20),
+            &",2".repeat(20),
+            " ".repeat(43)
+        );
+        assert_eq!(snippet(&input, x), expected);
+    }
+
+    #[test]
+    fn report_message() {
+        let input = "{\"a\": tru}";
+        assert_eq!(
+            report("Invalid literal", input, 6),
+            "Invalid literal at line 1, column 7\n{\"a\": tru}\n      ^"
+        );
+    }
+}
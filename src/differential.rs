@@ -0,0 +1,359 @@
+use crate::serde::PriceChange24Hr;
+use crate::ticker::{
+    Custom, CustomLazy, SerdeBorrowed, SerdeLazy, Sonic, Ticker, TickerParser, TickerView,
+};
+
+/// The JSON keys of an entry, in the order Binance sends them.
+const KEYS: [&str; 18] = [
+    "symbol",
+    "priceChange",
+    "priceChangePercent",
+    "lastPrice",
+    "lastQty",
+    "open",
+    "high",
+    "low",
+    "volume",
+    "amount",
+    "bidPrice",
+    "askPrice",
+    "openTime",
+    "closeTime",
+    "firstTradeId",
+    "tradeCount",
+    "strikePrice",
+    "exercisePrice",
+];
+
+/// Keys of the fields that are sent as JSON numbers, the rest are strings.
+const INTEGER_KEYS: [&str; 4] = ["openTime", "closeTime", "firstTradeId", "tradeCount"];
+
+/// Converts an entry of any parser to the owned entry of the `serde` parser.
+fn normalize(view: &impl TickerView) -> PriceChange24Hr {
+    let ticker = Ticker::from_view(view).unwrap();
+    PriceChange24Hr {
+        symbol: ticker.symbol,
+        price_change: ticker.price_change,
+        price_change_percent: ticker.price_change_percent,
+        last_price: ticker.last_price,
+        last_qty: ticker.last_qty,
+        open: ticker.open,
+        high: ticker.high,
+        low: ticker.low,
+        volume: ticker.volume,
+        amount: ticker.amount,
+        bid_price: ticker.bid_price,
+        ask_price: ticker.ask_price,
+        open_time: ticker.open_time,
+        close_time: ticker.close_time,
+        first_trade_id: ticker.first_trade_id,
+        trade_count: ticker.trade_count,
+        strike_price: ticker.strike_price,
+        exercise_price: ticker.exercise_price,
+    }
+}
+
+/// The fields of an entry, with the floats as bits so that they are compared bitwise.
+type Fields = (String, [u64; 13], [u64; 4]);
+
+fn fields(entries: &[PriceChange24Hr]) -> Vec<Fields> {
+    entries
+        .iter()
+        .map(|e| {
+            let floats = [
+                e.price_change,
+                e.price_change_percent,
+                e.last_price,
+                e.last_qty,
+                e.open,
+                e.high,
+                e.low,
+                e.volume,
+                e.amount,
+                e.bid_price,
+                e.ask_price,
+                e.strike_price,
+                e.exercise_price,
+            ];
+            let integers = [e.open_time, e.close_time, e.first_trade_id, e.trade_count];
+            (e.symbol.clone(), floats.map(f64::to_bits), integers)
+        })
+        .collect()
+}
+
+fn parse_normalized<P: TickerParser>(data: &str) -> Vec<PriceChange24Hr> {
+    let entries = P::parse(data).map_err(Into::into).unwrap();
+    entries.iter().map(normalize).collect()
+}
+
+/// Runs every parser on the data and checks that they all return the same entries as `serde`.
+/// `chunk_size` and `threads` are used by the streaming and parallel parsers.
+///
+/// `raw_symbols` are the symbols as written in the data. If some of them have escape sequences,
+/// each parser must behave as documented in `TickerView::symbol`.
+fn check(data: &str, raw_symbols: &[String], chunk_size: usize, threads: usize) {
+    let expected = fields(&crate::serde::parse(data).unwrap());
+    let mut expected_raw = expected.clone();
+    assert_eq!(expected_raw.len(), raw_symbols.len());
+    for (entry, symbol) in expected_raw.iter_mut().zip(raw_symbols) {
+        entry.0.clone_from(symbol);
+    }
+
+    // The parsers that borrow the symbol from the data, so they cannot decode it
+    let mut borrowed = vec![];
+    let escaped = raw_symbols.iter().any(|symbol| symbol.contains('\\'));
+    if escaped {
+        let errors = [
+            (
+                "serde_borrowed",
+                SerdeBorrowed::parse(data).err().map(Into::into),
+            ),
+            ("serde_lazy", SerdeLazy::parse(data).err().map(Into::into)),
+            ("sonic", Sonic::parse(data).err().map(Into::into)),
+        ];
+        for (name, error) in errors {
+            let error: anyhow::Error = error.unwrap_or_else(|| panic!("{name} on {data}"));
+            assert!(
+                error.to_string().contains("expected a borrowed string"),
+                "{name}: {error}"
+            );
+        }
+        let entries = crate::custom_lazy::from_str::<Vec<crate::serde_lazy::PriceChange24Hr>>(data);
+        assert!(
+            entries.is_err(),
+            "custom_lazy::from_str (serde_lazy) on {data}"
+        );
+    } else {
+        borrowed.push(("serde_borrowed", parse_normalized::<SerdeBorrowed>(data)));
+        borrowed.push(("serde_lazy", parse_normalized::<SerdeLazy>(data)));
+        borrowed.push(("sonic", parse_normalized::<Sonic>(data)));
+        let entries: Vec<crate::serde_lazy::PriceChange24Hr> =
+            crate::custom_lazy::from_str(data).unwrap();
+        borrowed.push((
+            "custom_lazy::from_str (serde_lazy)",
+            entries.iter().map(normalize).collect(),
+        ));
+    }
+    for (name, entries) in borrowed {
+        assert_eq!(fields(&entries), expected, "{name} on {data}");
+    }
+
+    let entries: Vec<PriceChange24Hr> = crate::custom_lazy::from_str(data).unwrap();
+    assert_eq!(
+        fields(&entries),
+        expected,
+        "custom_lazy::from_str on {data}"
+    );
+
+    // The parsers that return the symbols without decoding them
+    let mut results = vec![
+        ("custom", parse_normalized::<Custom>(data)),
+        ("custom_lazy", parse_normalized::<CustomLazy>(data)),
+    ];
+
+    let mut parser = crate::custom_stream::Parser::new();
+    let mut entries = Vec::new();
+    for chunk in data.as_bytes().chunks(chunk_size) {
+        for entry in parser.push(chunk).unwrap() {
+            entries.push(normalize(&entry.unwrap()));
+        }
+    }
+    parser.finish().unwrap();
+    results.push(("custom_stream", entries));
+
+    let entries = crate::custom_parallel::parse_parallel(data, threads).unwrap();
+    results.push(("custom_parallel", entries.iter().map(normalize).collect()));
+
+    for (name, entries) in results {
+        assert_eq!(fields(&entries), expected_raw, "{name} on {data}");
+    }
+}
+
+#[test]
+fn sample_data() {
+    for data in [
+        include_str!("../single.txt"),
+        include_str!("../request.txt"),
+    ] {
+        // The recorded symbols have no escape sequences, so they are the same once decoded
+        assert!(!data.contains('\\'));
+        let symbols: Vec<_> = crate::serde::parse(data)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.symbol)
+            .collect();
+        for (chunk_size, threads) in [(1, 1), (13, 2), (4096, 8)] {
+            check(data, &symbols, chunk_size, threads);
+        }
+    }
+}
+
+/// A linear congruential generator, enough to make varied payloads without a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 11
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, values: &[&'a str]) -> &'a str {
+        values[self.below(values.len())]
+    }
+
+    fn digits(&mut self, out: &mut String, count: usize) {
+        for _ in 0..count {
+            out.push((b'0' + self.below(10) as u8) as char);
+        }
+    }
+}
+
+/// A symbol as written in JSON. With `escapes`, the last character may be an escape sequence.
+fn random_symbol(rng: &mut Rng, escapes: bool) -> String {
+    let mut symbol = rng
+        .pick(&["BTC", "ETH", "BNB", "SOL", "XRP", "DOGE"])
+        .to_string();
+    symbol.push('-');
+    rng.digits(&mut symbol, 6);
+    symbol.push('-');
+    let count = 1 + rng.below(6);
+    rng.digits(&mut symbol, count);
+    symbol.push('-');
+    // Multi-byte characters, to cover entries and chunks that split them
+    let last = ["C", "P", "é", "€", "𝄞"];
+    let escaped = [
+        r"\u00e9",
+        r"\u20AC",
+        r"\ud834\udd1e",
+        r#"\""#,
+        r"\\",
+        r"\/",
+        r"\n",
+    ];
+    if escapes && rng.below(2) == 0 {
+        symbol.push_str(rng.pick(&escaped));
+    } else {
+        symbol.push_str(rng.pick(&last));
+    }
+    symbol
+}
+
+/// A finite number in one of the forms Binance uses, or a less common one.
+fn random_float(rng: &mut Rng) -> String {
+    let mut number = String::new();
+    if rng.below(3) == 0 {
+        number.push('-');
+    }
+    match rng.below(4) {
+        0 => number.push('0'),
+        _ => {
+            number.push((b'1' + rng.below(9) as u8) as char);
+            let count = rng.below(20);
+            rng.digits(&mut number, count);
+        }
+    }
+    if rng.below(2) == 0 {
+        number.push('.');
+        let count = 1 + rng.below(20);
+        rng.digits(&mut number, count);
+    }
+    if rng.below(5) == 0 {
+        number.push_str(rng.pick(&["e", "E", "e-", "E+"]));
+        let count = 1 + rng.below(2);
+        rng.digits(&mut number, count);
+    }
+    number
+}
+
+fn random_integer(rng: &mut Rng) -> u64 {
+    match rng.below(8) {
+        0 => 0,
+        1 => u64::MAX,
+        _ => (rng.next() << 11 | rng.next()) >> rng.below(64),
+    }
+}
+
+/// An array of `entries` entries, with random values, whitespace and order of the keys. Also
+/// returns the symbols of the entries, as they are written in the payload.
+fn random_payload(rng: &mut Rng, entries: usize) -> (String, Vec<String>) {
+    let whitespace = |rng: &mut Rng| rng.pick(&["", "", "", " ", "\n", "\t", " \r\n  "]);
+    let escapes = rng.below(4) == 0;
+    let mut symbols = Vec::with_capacity(entries);
+    let mut payload = String::new();
+    payload.push_str(whitespace(rng));
+    payload.push('[');
+
+    for i in 0..entries {
+        if i > 0 {
+            payload.push(',');
+        }
+        payload.push_str(whitespace(rng));
+        payload.push('{');
+
+        let mut keys = KEYS;
+        if rng.below(2) == 0 {
+            for i in (1..keys.len()).rev() {
+                keys.swap(i, rng.below(i + 1));
+            }
+        }
+        for (j, key) in keys.iter().enumerate() {
+            if j > 0 {
+                payload.push(',');
+            }
+            let value = if *key == "symbol" {
+                symbols.push(random_symbol(rng, escapes));
+                format!("\"{}\"", symbols[i])
+            } else if INTEGER_KEYS.contains(key) {
+                random_integer(rng).to_string()
+            } else {
+                format!("\"{}\"", random_float(rng))
+            };
+            payload.push_str(&format!(
+                "{}\"{key}\"{}:{}{value}{}",
+                whitespace(rng),
+                whitespace(rng),
+                whitespace(rng),
+                whitespace(rng)
+            ));
+        }
+        payload.push('}');
+        payload.push_str(whitespace(rng));
+    }
+
+    payload.push(']');
+    payload.push_str(whitespace(rng));
+    (payload, symbols)
+}
+
+#[test]
+fn random_data() {
+    let mut rng = Rng(0x2545f491);
+    for _ in 0..300 {
+        let entries = rng.below(20);
+        let (payload, symbols) = random_payload(&mut rng, entries);
+        let chunk_size = 1 + rng.below(64);
+        let threads = 1 + rng.below(8);
+        check(&payload, &symbols, chunk_size, threads);
+    }
+}
+
+/// Payloads with more entries than `request.txt`, split between several threads.
+#[test]
+fn random_large_data() {
+    let mut rng = Rng(0x9e3779b9);
+    for _ in 0..4 {
+        let entries = 2000 + rng.below(2000);
+        let (payload, symbols) = random_payload(&mut rng, entries);
+        let chunk_size = 1 + rng.below(4096);
+        let threads = 2 + rng.below(4);
+        check(&payload, &symbols, chunk_size, threads);
+    }
+}
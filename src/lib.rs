@@ -88,18 +88,30 @@ pub mod sonic;
 ///
 /// I reused most of this code from a personal project (git-dashboard), which is not yet publicly
 /// available.
-/// This parser performs (unsurprisingly) poorly when compared to more mature solutions like serde
-/// or sonic-rs.
 ///
-/// The complexity of this parser scales linearly with `N`, but the amount of work it performs to
-/// parse a single entry is reasonably large, making it not very suitable for a low-latency
-/// environment.
+/// The AST is stored in a single flat arena: one `Vec` of nodes in document order, where each node
+/// only keeps its type, its span in the input and the indices of its parent and of its next
+/// sibling. `ValueAst` and friends are lightweight handles into that arena, so parsing a message
+/// does a single allocation no matter how many objects and arrays it contains. The parser reads
+/// the structural characters directly from the input and only uses the tokenizer for scalars.
+///
+/// This roughly halved the time needed to parse an entry. Scanning strings 8 bytes at a time and
+/// inlining the hot paths of the parser brought it to about 1.3x slower than `serde_borrowed` on
+/// `single.txt` (0.63µs against 0.48µs) and 1.4x slower on `request.txt` (0.83ms against
+/// 0.59ms), so it does not beat `serde_borrowed` yet: building the AST alone takes about as long
+/// as `serde_borrowed`, and walking it to build the entries comes on top.
+///
+/// Any value of the AST can be written back as JSON, compact or pretty printed, with
+/// `ValueAst::write_to` or `ValueAst::display`. Numbers and strings are copied verbatim from the
+/// input.
+///
+/// `EditableAst` allows to set, insert and remove values before writing the document back, e.g. to
+/// redact or add fields. Only the objects and arrays that contain an edit are copied, everything
+/// else keeps borrowing from the input.
 ///
 /// Possible improvements:
 ///  - Parse the JSON lazily based on the data actually needed by the user.
 ///  - Use faster parsing using vectorization.
-///  - Keep less metadata on each value.
-///  - Reduce the number of allocations needed to represent a value.
 pub mod custom;
 
 /// Implements a custom hand-crafted lazy parser. This parser implements a different concept than
@@ -136,16 +148,95 @@ pub mod custom;
 /// Note that, with this parser, even if the message had 1 million entries, we only needed to parse
 /// part of the first message, which could be a really big advantage.
 ///
-/// This parser is zero-alloc.
+/// The same lookup can be written as a JSON Pointer (RFC 6901) with
+/// `document.pointer("/0/symbol")`, which is handy when the paths come from a configuration file.
+/// `custom::ValueAst::pointer` does the same on the eager AST.
+///
+/// For lookups over many entries, `Query` compiles JSONPath-style expressions such as
+/// `$[?(@.tradeCount > 0)].lastPrice` or `$[0:10].symbol`. `Document::query` returns the matching
+/// nodes as an iterator, reading the message only as far as the results that are requested.
+///
+/// Structs can also be filled directly from an object with `#[derive(LazyExtract)]` (implemented
+/// in the `binance-derive` crate), which looks up all the fields in a single pass instead of one
+/// `get_key` call per field.
+///
+/// Any `serde::Deserialize` type can also be read from a node, or from the whole message with
+/// `custom_lazy::from_str`, so the structs of `serde_borrowed` and `serde_lazy` can be filled by
+/// this parser. On the benchmark this is within about 10-35% of `serde_json` for the same structs.
+///
+/// This parser is zero-alloc, unless the user requests to decode a string containing escape
+/// sequences.
 ///
 /// This parser is motivated by <https://arxiv.org/abs/2312.17149>
 ///
-/// Possible improvements:
-///  - Make the Array and Object elements remember the last cursor of the entry they last read.
-///    This is not currently implemented, so if I asked an array object for the 88th entry and then
-///    for the 89th, it would re-parse the array from the beginning, making it rather inefficient.
-///  - Use faster parsing using vectorization.
+/// Array and Object elements remember the position of the entry they last returned. If I asked an
+/// array for the 88th entry and then for the 89th, the second lookup resumes from the 88th entry
+/// instead of re-parsing the array from the beginning. Visiting every entry is best done with
+/// `Array::iter` or `Object::iter` though.
+///
+/// Optionally, a `StructuralIndex` of the message can be built ahead of time using SIMD
+/// instructions, following the first stage of simdjson (<https://arxiv.org/abs/1902.08318>). A
+/// document created with `Document::with_index` uses it to skip over strings, objects and arrays
+/// by jumping between structural characters instead of inspecting every byte. Building the index
+/// requires a pass over the whole message, so it pays off when a large part of the message is
+/// going to be navigated.
 pub mod custom_lazy;
 
+/// A push parser for chunked HTTP bodies, built on top of `custom`.
+///
+/// The other parsers need the whole message before they can start. With this one I push the body
+/// chunk by chunk as it arrives from the network, and every entry is returned as soon as its
+/// closing brace is received, so the first entries can be used while the rest of the response is
+/// still downloading. Entries may straddle any number of chunks.
+///
+/// Between chunks, the parser only tracks the structure of the top-level array and keeps the bytes
+/// of the entry that is not complete yet. Each complete entry is parsed with `custom`, and the
+/// offsets of the errors are relative to the whole body.
+pub mod custom_stream;
+
+/// Parses large messages on several threads with `custom_parallel::parse_parallel`.
+///
+/// The top-level array is first split into entries with the same scanner as `custom_stream`,
+/// which uses the `StructuralIndex` of `custom_lazy` to jump over the entries. The entries are
+/// then divided into contiguous batches, each parsed with `custom` on its own scoped thread, and
+/// the results are concatenated in order. There is no thread pool, so the threads are spawned on
+/// every call and small messages are parsed on the current thread.
+///
+/// The split and the per-entry parsing add about 30% of work compared to `custom::parse`, so it
+/// only pays off with 2 or more cores available.
+pub mod custom_parallel;
+
+/// The `TickerParser` and `TickerView` traits, implemented for all the parsers above, so the code
+/// that uses the entries does not depend on the parser that produced them.
+///
+/// Each parser has a marker type (`ticker::Serde`, `ticker::Custom`, ...) implementing
+/// `TickerParser`, whose entries implement `TickerView`. The getters convert the values on
+/// demand, so the lazy parsers keep their advantage when only a few fields are read.
+///
+/// To choose the parser at runtime, e.g. from the `--parser` option of the binary, `ParserKind`
+/// names each parser and `ticker::parse_with` returns the entries as owned `Ticker` values.
+pub mod ticker;
+
+/// Helpers to turn the byte offset of a parsing error into a line and column, and to show the
+/// part of the input where the error was found. Used by the errors of `custom` and
+/// `custom_lazy`.
+pub mod diagnostics;
+
+/// Checks that all the parsers return the same entries for the sample data and for random
+/// payloads.
+///
+/// The only recorded fixtures are `single.txt` and `request.txt`. Payloads larger than those are
+/// only covered by the random payloads of `random_large_data`.
+#[cfg(test)]
+mod differential;
+
+/// JSON Pointer parsing, used by the `pointer` lookups of `custom` and `custom_lazy`.
+mod pointer;
+
 /// Development utilities used by more than 1 parser.
 mod utils;
+
+pub use utils::{LazyF64, LazyU64};
+
+// Lets the code generated by `binance-derive`, which refers to `::binance`, be used in this crate.
+extern crate self as binance;
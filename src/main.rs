@@ -1,48 +1,85 @@
-fn get_ticker_price_change(base_endpoint: &str) -> anyhow::Result<String> {
-    let ticker_endpoint = format!("{base_endpoint}/eapi/v1/ticker");
-    let request = reqwest::blocking::get(ticker_endpoint)?;
-    let request = request.error_for_status()?;
+use std::io::Read;
 
-    Ok(request.text()?)
-}
+use binance::ticker::ParserKind;
 
-fn main() -> anyhow::Result<()> {
-    let endpoint_result = get_ticker_price_change("https://eapi.binance.com")?;
+const USAGE: &str = "\
+Usage: binance [--parser <serde|serde_borrowed|serde_lazy|sonic|custom|custom_lazy>]
 
-    let price_changes = binance::serde::parse(&endpoint_result)?;
-    for i in &price_changes {
-        println!("{:#?}", i);
-    }
+Prints the entries returned by the ticker endpoint using the given parser. Without --parser, the
+entries are printed by the streaming parser while they are downloaded.";
 
-    let price_changes = binance::serde_borrowed::parse(&endpoint_result)?;
-    for i in &price_changes {
-        println!("{:#?}", i);
+/// Returns the parser selected with `--parser`, if any.
+fn parse_args() -> anyhow::Result<Option<ParserKind>> {
+    let mut args = std::env::args().skip(1);
+    let mut parser = None;
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--parser" => args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for --parser\n\n{USAGE}"))?,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => match arg.strip_prefix("--parser=") {
+                Some(value) => value.to_string(),
+                None => anyhow::bail!("Unknown argument {arg}\n\n{USAGE}"),
+            },
+        };
+        parser = Some(value.parse()?);
     }
+    Ok(parser)
+}
 
-    let price_changes = binance::serde_lazy::parse(&endpoint_result)?;
-    for i in &price_changes {
-        println!("{:#?}", i);
-    }
+fn get_ticker_price_change(base_endpoint: &str) -> anyhow::Result<reqwest::blocking::Response> {
+    let ticker_endpoint = format!("{base_endpoint}/eapi/v1/ticker");
+    let request = reqwest::blocking::get(ticker_endpoint)?;
+    Ok(request.error_for_status()?)
+}
 
-    let price_changes = binance::sonic::parse(&endpoint_result)?;
-    for i in &price_changes {
-        println!("{:#?}", i);
+/// Reads the body, printing every entry with the streaming parser as soon as it arrives.
+fn read_streaming(mut request: reqwest::blocking::Response) -> anyhow::Result<String> {
+    let mut body = Vec::new();
+    let mut parser = binance::custom_stream::Parser::new();
+    let mut chunk = [0; 8192];
+    let report = |e: binance::custom::Error, body: &[u8]| {
+        anyhow::anyhow!("{}", e.report(&String::from_utf8_lossy(body)))
+    };
+    loop {
+        let read = request.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+        let entries = parser.push(&chunk[..read]).map_err(|e| report(e, &body))?;
+        for entry in entries {
+            println!("{:#?}", entry.map_err(|e| report(e, &body))?);
+        }
     }
+    parser.finish().map_err(|e| report(e, &body))?;
 
-    let price_changes = binance::custom::parse(&endpoint_result)?;
-    for i in &price_changes {
-        println!("{:#?}", i);
-    }
+    Ok(String::from_utf8(body)?)
+}
+
+fn main() -> anyhow::Result<()> {
+    let parser = parse_args()?;
+    let request = get_ticker_price_change("https://eapi.binance.com")?;
+
+    let endpoint_result = match parser {
+        Some(parser) => {
+            let endpoint_result = request.text()?;
+            for ticker in binance::ticker::parse_with(parser, &endpoint_result)? {
+                println!("{ticker:#?}");
+            }
+            endpoint_result
+        }
+        None => read_streaming(request)?,
+    };
 
     // An example showing how to use the lazy API.
     let document = binance::custom_lazy::Document::new(&endpoint_result);
-    let symbol = document
-        .as_array()?
-        .get_index(0)?
-        .as_object()?
-        .get_key("symbol")?
-        .as_string()?
-        .get_value()?;
+    let symbol = (|| document.pointer("/0/symbol")?.as_string()?.get_value())()
+        .map_err(|e| anyhow::anyhow!("{}", e.report(&endpoint_result)))?;
     println!("symbol: {symbol}");
 
     Ok(())
@@ -0,0 +1,119 @@
+//! JSON Pointer (RFC 6901) parsing, shared by the lookups of `custom` and `custom_lazy`.
+
+use std::borrow::Cow;
+
+/// Splits a pointer into its reference tokens, decoding the `~1` and `~0` escape sequences.
+/// Returns `None` if the pointer is not valid.
+///
+/// The empty pointer has no tokens and refers to the whole document.
+pub(crate) fn tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let rest = match pointer.strip_prefix('/') {
+        Some(rest) => Some(rest),
+        None if pointer.is_empty() => None,
+        None => return None,
+    };
+
+    let valid_escapes = pointer
+        .match_indices('~')
+        .all(|(i, _)| matches!(pointer.as_bytes().get(i + 1), Some(b'0' | b'1')));
+    if !valid_escapes {
+        return None;
+    }
+
+    Some(
+        rest.into_iter()
+            .flat_map(|rest| rest.split('/'))
+            .map(|token| {
+                if token.contains('~') {
+                    // `~1` must be decoded first, so `~01` becomes `~1` and not `/`
+                    Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+                } else {
+                    Cow::Borrowed(token)
+                }
+            }),
+    )
+}
+
+/// Parses a token as an array index. Leading zeros and signs are not allowed, and `-` (the
+/// element after the last one) never refers to an existing element.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let valid = match token.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    valid.then(|| token.parse().ok()).flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn collect(pointer: &str) -> Option<Vec<Cow<'_, str>>> {
+        tokens(pointer).map(Iterator::collect)
+    }
+
+    #[test]
+    fn split_tokens() {
+        assert_eq!(collect(""), Some(vec![]));
+        assert_eq!(collect("/"), Some(vec!["".into()]));
+        assert_eq!(
+            collect("/0/symbol"),
+            Some(vec!["0".into(), "symbol".into()])
+        );
+        assert_eq!(
+            collect("/a~1b/m~0n"),
+            Some(vec!["a/b".into(), "m~n".into()])
+        );
+        assert_eq!(collect("/~01"), Some(vec!["~1".into()]));
+
+        assert_eq!(collect("0/symbol"), None);
+        assert_eq!(collect("/a~2"), None);
+        assert_eq!(collect("/a~"), None);
+    }
+
+    #[test]
+    fn same_values_in_custom_and_custom_lazy() {
+        let input = r#"{"a\/b": 1, "a/b": 2, "\u00e9": [3, {"~": 4}], "\"": 5, "x": {"y": 6}}"#;
+        let ast = crate::custom::parse_json(input).unwrap();
+        let document = crate::custom_lazy::Document::new(input);
+
+        for pointer in [
+            "/a~1b", "/é", "/é/1/~0", "/\"", "/x/y", "/x", "/y", "/é/2", "",
+        ] {
+            let custom = ast.root().pointer(input, pointer);
+            let custom = custom.map(|v| v.get_raw_string(input)).ok();
+            let lazy = document.pointer(pointer).and_then(|v| v.raw_slice()).ok();
+            assert_eq!(custom, lazy, "{pointer}");
+        }
+        assert_eq!(
+            ast.root()
+                .pointer(input, "/a~1b")
+                .unwrap()
+                .get_raw_string(input),
+            "1"
+        );
+
+        // A path into a scalar is the same error in both, whether the token is a key or an index
+        for pointer in ["/x/y/z", "/x/y/0", "/a~1b/0"] {
+            let custom = ast.root().pointer(input, pointer).unwrap_err();
+            let Err(lazy) = document.pointer(pointer) else {
+                panic!("{pointer} resolved in custom_lazy");
+            };
+            assert_eq!(custom.kind(), &crate::custom::ErrorKind::NotAContainer);
+            assert_eq!(lazy.kind(), &crate::custom_lazy::ErrorKind::NotAContainer);
+            assert_eq!(custom.kind().to_string(), lazy.kind().to_string());
+            assert_eq!(custom.offset(), lazy.offset(), "{pointer}");
+        }
+    }
+
+    #[test]
+    fn array_indices() {
+        assert_eq!(array_index("0"), Some(0));
+        assert_eq!(array_index("1234"), Some(1234));
+
+        for token in ["", "-", "01", "+1", "-1", "1a", "99999999999999999999999"] {
+            assert_eq!(array_index(token), None, "{token}");
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use crate::custom_lazy::{self, FromNode};
+use crate::utils::{LazyF64, LazyU64};
+
+/// A parser for the data returned by the `GET /eapi/v1/ticker` endpoint. Each parser module has a
+/// marker type implementing it, so the parser can be chosen with a generic parameter:
+///
+/// ```rust
+/// use binance::ticker::{Custom, TickerParser, TickerView};
+///
+/// fn total_volume<P: TickerParser>(data: &str) -> anyhow::Result<f64> {
+///     let entries = P::parse(data).map_err(Into::into)?;
+///     Ok(entries.iter().filter_map(|entry| entry.volume()).sum())
+/// }
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let data = include_str!("../single.txt");
+/// assert_eq!(total_volume::<Custom>(data)?, 17.56);
+/// # Ok(())
+/// # }
+/// ```
+pub trait TickerParser {
+    /// An entry of the array, which may borrow from the data.
+    type Entry<'a>: TickerView;
+    type Error: Into<anyhow::Error>;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error>;
+}
+
+/// Typed access to the fields of an entry, whatever the parser that produced it.
+///
+/// The lazy parsers only convert a value when its getter is called, so every getter returns
+/// `None` if the value is missing or is not valid for its type.
+pub trait TickerView {
+    /// The symbol of the entry. Symbols with escape sequences are only decoded by `serde` (and
+    /// by `custom_lazy::from_str` into an owned `String`). `custom`, `custom_lazy`,
+    /// `custom_stream` and `custom_parallel` return the escaped text as it is in the data, and
+    /// `serde_borrowed`, `serde_lazy` and `sonic` fail to parse the entry.
+    fn symbol(&self) -> Option<&str>;
+    fn price_change(&self) -> Option<f64>;
+    fn price_change_percent(&self) -> Option<f64>;
+    fn last_price(&self) -> Option<f64>;
+    fn last_qty(&self) -> Option<f64>;
+    fn open(&self) -> Option<f64>;
+    fn high(&self) -> Option<f64>;
+    fn low(&self) -> Option<f64>;
+    fn volume(&self) -> Option<f64>;
+    fn amount(&self) -> Option<f64>;
+    fn bid_price(&self) -> Option<f64>;
+    fn ask_price(&self) -> Option<f64>;
+    fn open_time(&self) -> Option<u64>;
+    fn close_time(&self) -> Option<u64>;
+    fn first_trade_id(&self) -> Option<u64>;
+    fn trade_count(&self) -> Option<u64>;
+    fn strike_price(&self) -> Option<f64>;
+    fn exercise_price(&self) -> Option<f64>;
+}
+
+/// The `serde` parser.
+pub struct Serde;
+/// The `serde_borrowed` parser.
+pub struct SerdeBorrowed;
+/// The `serde_lazy` parser.
+pub struct SerdeLazy;
+/// The `sonic` parser.
+pub struct Sonic;
+/// The `custom` parser.
+pub struct Custom;
+/// The `custom_lazy` parser. The entries are the objects of the array, which are only checked
+/// to be objects: the fields are looked up when their getters are called.
+pub struct CustomLazy;
+
+impl TickerParser for Serde {
+    type Entry<'a> = crate::serde::PriceChange24Hr;
+    type Error = anyhow::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        crate::serde::parse(data)
+    }
+}
+
+impl TickerParser for SerdeBorrowed {
+    type Entry<'a> = crate::serde_borrowed::PriceChange24Hr<'a>;
+    type Error = anyhow::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        crate::serde_borrowed::parse(data)
+    }
+}
+
+impl TickerParser for SerdeLazy {
+    type Entry<'a> = crate::serde_lazy::PriceChange24Hr<'a>;
+    type Error = anyhow::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        crate::serde_lazy::parse(data)
+    }
+}
+
+impl TickerParser for Sonic {
+    type Entry<'a> = crate::sonic::PriceChange24Hr<'a>;
+    type Error = anyhow::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        crate::sonic::parse(data)
+    }
+}
+
+impl TickerParser for Custom {
+    type Entry<'a> = crate::custom::PriceChange24Hr<'a>;
+    type Error = crate::custom::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        crate::custom::parse(data)
+    }
+}
+
+impl TickerParser for CustomLazy {
+    type Entry<'a> = custom_lazy::Object<'a>;
+    type Error = custom_lazy::Error;
+
+    fn parse(data: &str) -> Result<Vec<Self::Entry<'_>>, Self::Error> {
+        custom_lazy::Document::new(data)
+            .as_array()?
+            .iter()
+            .map(|entry| entry?.as_object())
+            .collect()
+    }
+}
+
+/// The types used for floating point fields by the different parsers.
+trait AsF64 {
+    fn as_f64(&self) -> Option<f64>;
+}
+
+impl AsF64 for f64 {
+    fn as_f64(&self) -> Option<f64> {
+        Some(*self)
+    }
+}
+
+impl AsF64 for &str {
+    fn as_f64(&self) -> Option<f64> {
+        self.parse().ok()
+    }
+}
+
+impl AsF64 for LazyF64<'_> {
+    fn as_f64(&self) -> Option<f64> {
+        (*self).try_into().ok()
+    }
+}
+
+/// The types used for integer fields by the different parsers.
+trait AsU64 {
+    fn as_u64(&self) -> Option<u64>;
+}
+
+impl AsU64 for u64 {
+    fn as_u64(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+impl AsU64 for usize {
+    fn as_u64(&self) -> Option<u64> {
+        (*self).try_into().ok()
+    }
+}
+
+impl AsU64 for LazyU64<'_> {
+    fn as_u64(&self) -> Option<u64> {
+        (*self).try_into().ok()
+    }
+}
+
+/// Implements `TickerView` for the entries of the parsers that have a `PriceChange24Hr` struct,
+/// which all use the same field names.
+macro_rules! impl_ticker_view {
+    ($($ty:ty),*) => {$(
+        impl TickerView for $ty {
+            fn symbol(&self) -> Option<&str> {
+                Some(&*self.symbol)
+            }
+            impl_ticker_view!(@f64 price_change, price_change_percent, last_price, last_qty, open,
+                high, low, volume, amount, bid_price, ask_price, strike_price, exercise_price);
+            impl_ticker_view!(@u64 open_time, close_time, first_trade_id, trade_count);
+        }
+    )*};
+    (@f64 $($field:ident),*) => {$(
+        fn $field(&self) -> Option<f64> {
+            self.$field.as_f64()
+        }
+    )*};
+    (@u64 $($field:ident),*) => {$(
+        fn $field(&self) -> Option<u64> {
+            self.$field.as_u64()
+        }
+    )*};
+}
+
+impl_ticker_view!(
+    crate::serde::PriceChange24Hr,
+    crate::serde_borrowed::PriceChange24Hr<'_>,
+    crate::serde_lazy::PriceChange24Hr<'_>,
+    crate::sonic::PriceChange24Hr<'_>,
+    crate::custom::PriceChange24Hr<'_>
+);
+
+/// Implements the getters of `TickerView` for `custom_lazy::Object`, looking up the given keys.
+macro_rules! impl_lazy_getters {
+    ($ty:ty, $lazy:ident, $($field:ident: $key:literal),*) => {$(
+        fn $field(&self) -> Option<$ty> {
+            let value = self.get_key($key).and_then($lazy::from_node).ok()?;
+            value.try_into().ok()
+        }
+    )*};
+}
+
+impl TickerView for custom_lazy::Object<'_> {
+    fn symbol(&self) -> Option<&str> {
+        self.get_key("symbol").and_then(<&str>::from_node).ok()
+    }
+
+    impl_lazy_getters!(
+        f64,
+        LazyF64,
+        price_change: "priceChange",
+        price_change_percent: "priceChangePercent",
+        last_price: "lastPrice",
+        last_qty: "lastQty",
+        open: "open",
+        high: "high",
+        low: "low",
+        volume: "volume",
+        amount: "amount",
+        bid_price: "bidPrice",
+        ask_price: "askPrice",
+        strike_price: "strikePrice",
+        exercise_price: "exercisePrice"
+    );
+    impl_lazy_getters!(
+        u64,
+        LazyU64,
+        open_time: "openTime",
+        close_time: "closeTime",
+        first_trade_id: "firstTradeId",
+        trade_count: "tradeCount"
+    );
+}
+
+/// An entry with every field converted, independent of the parser that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ticker {
+    pub symbol: String,
+    pub price_change: f64,
+    pub price_change_percent: f64,
+    pub last_price: f64,
+    pub last_qty: f64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume: f64,
+    pub amount: f64,
+    pub bid_price: f64,
+    pub ask_price: f64,
+    pub open_time: u64,
+    pub close_time: u64,
+    pub first_trade_id: u64,
+    pub trade_count: u64,
+    pub strike_price: f64,
+    pub exercise_price: f64,
+}
+
+/// Returned by `Ticker::from_view` when a field is missing or not valid for its type.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Missing or invalid field {0}")]
+pub struct InvalidField(pub &'static str);
+
+impl Ticker {
+    /// Converts every field of the entry.
+    pub fn from_view(view: &impl TickerView) -> Result<Self, InvalidField> {
+        fn field<T>(value: Option<T>, key: &'static str) -> Result<T, InvalidField> {
+            value.ok_or(InvalidField(key))
+        }
+
+        Ok(Self {
+            symbol: field(view.symbol(), "symbol")?.to_string(),
+            price_change: field(view.price_change(), "priceChange")?,
+            price_change_percent: field(view.price_change_percent(), "priceChangePercent")?,
+            last_price: field(view.last_price(), "lastPrice")?,
+            last_qty: field(view.last_qty(), "lastQty")?,
+            open: field(view.open(), "open")?,
+            high: field(view.high(), "high")?,
+            low: field(view.low(), "low")?,
+            volume: field(view.volume(), "volume")?,
+            amount: field(view.amount(), "amount")?,
+            bid_price: field(view.bid_price(), "bidPrice")?,
+            ask_price: field(view.ask_price(), "askPrice")?,
+            open_time: field(view.open_time(), "openTime")?,
+            close_time: field(view.close_time(), "closeTime")?,
+            first_trade_id: field(view.first_trade_id(), "firstTradeId")?,
+            trade_count: field(view.trade_count(), "tradeCount")?,
+            strike_price: field(view.strike_price(), "strikePrice")?,
+            exercise_price: field(view.exercise_price(), "exercisePrice")?,
+        })
+    }
+}
+
+/// Selects a parser at runtime. Parsed from and displayed as the name of the parser module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserKind {
+    Serde,
+    SerdeBorrowed,
+    SerdeLazy,
+    Sonic,
+    Custom,
+    CustomLazy,
+}
+
+impl ParserKind {
+    pub const ALL: [ParserKind; 6] = [
+        ParserKind::Serde,
+        ParserKind::SerdeBorrowed,
+        ParserKind::SerdeLazy,
+        ParserKind::Sonic,
+        ParserKind::Custom,
+        ParserKind::CustomLazy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ParserKind::Serde => "serde",
+            ParserKind::SerdeBorrowed => "serde_borrowed",
+            ParserKind::SerdeLazy => "serde_lazy",
+            ParserKind::Sonic => "sonic",
+            ParserKind::Custom => "custom",
+            ParserKind::CustomLazy => "custom_lazy",
+        }
+    }
+}
+
+impl std::fmt::Display for ParserKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown parser {0}, expected one of serde, serde_borrowed, serde_lazy, sonic, custom or custom_lazy")]
+pub struct UnknownParser(pub String);
+
+impl std::str::FromStr for ParserKind {
+    type Err = UnknownParser;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParserKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| UnknownParser(s.to_string()))
+    }
+}
+
+/// Parses the data with the parser selected at runtime and converts every entry to a `Ticker`.
+pub fn parse_with(kind: ParserKind, data: &str) -> anyhow::Result<Vec<Ticker>> {
+    fn parse<P: TickerParser>(data: &str) -> anyhow::Result<Vec<Ticker>> {
+        let entries = P::parse(data).map_err(Into::into)?;
+        let tickers = entries.iter().map(Ticker::from_view);
+        Ok(tickers.collect::<Result<_, _>>()?)
+    }
+
+    match kind {
+        ParserKind::Serde => parse::<Serde>(data),
+        ParserKind::SerdeBorrowed => parse::<SerdeBorrowed>(data),
+        ParserKind::SerdeLazy => parse::<SerdeLazy>(data),
+        ParserKind::Sonic => parse::<Sonic>(data),
+        ParserKind::Custom => parse::<Custom>(data),
+        ParserKind::CustomLazy => parse::<CustomLazy>(data),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Formats every field of every entry, to compare the parsers.
+    fn summary<P: TickerParser>(data: &str) -> Vec<String> {
+        let entries = P::parse(data).map_err(Into::into).unwrap();
+        entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+                    e.symbol(),
+                    e.price_change(),
+                    e.price_change_percent(),
+                    e.last_price(),
+                    e.last_qty(),
+                    e.open(),
+                    e.high(),
+                    e.low(),
+                    e.volume(),
+                    e.amount(),
+                    e.bid_price(),
+                    e.ask_price(),
+                    e.open_time(),
+                    e.close_time(),
+                    e.first_trade_id(),
+                    e.trade_count(),
+                    e.strike_price(),
+                    e.exercise_price(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parsers_agree() {
+        let single = summary::<Custom>(include_str!("../single.txt"));
+        assert_eq!(
+            single,
+            [
+                "Some(\"BTC-241206-115000-C\") Some(-45.0) Some(-0.4736) Some(50.0) Some(0.1) \
+              Some(95.0) Some(95.0) Some(50.0) Some(17.56) Some(1282.55) Some(50.0) Some(60.0) \
+              Some(1732905664238) Some(1732980312615) Some(314) Some(77) Some(115000.0) \
+              Some(96638.25170213)"
+            ]
+        );
+
+        let data = include_str!("../request.txt");
+        let expected = summary::<Serde>(data);
+        assert_eq!(summary::<SerdeBorrowed>(data), expected);
+        assert_eq!(summary::<SerdeLazy>(data), expected);
+        assert_eq!(summary::<Sonic>(data), expected);
+        assert_eq!(summary::<Custom>(data), expected);
+        assert_eq!(summary::<CustomLazy>(data), expected);
+    }
+
+    #[test]
+    fn parser_kind() {
+        for kind in ParserKind::ALL {
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+        assert_eq!(
+            "simd".parse::<ParserKind>(),
+            Err(UnknownParser("simd".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_with_every_kind() {
+        let data = include_str!("../request.txt");
+        let expected = parse_with(ParserKind::Serde, data).unwrap();
+        assert_eq!(expected.len(), 1134);
+        for kind in ParserKind::ALL {
+            assert_eq!(parse_with(kind, data).unwrap(), expected, "{kind}");
+        }
+
+        let invalid = include_str!("../single.txt").replace("\"95\"", "\"x\"");
+        let error = parse_with(ParserKind::CustomLazy, &invalid).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<InvalidField>(),
+            Some(&InvalidField("open"))
+        );
+    }
+}
@@ -33,3 +33,10 @@ impl std::fmt::Debug for LazyU64<'_> {
         write!(f, "{:?}", result)
     }
 }
+
+/// Whether `byte` is whitespace between JSON tokens. Unlike `u8::is_ascii_whitespace`, form feed
+/// is not whitespace in JSON.
+#[inline(always)]
+pub(crate) fn is_json_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\n' | b'\r' | b'\t')
+}